@@ -0,0 +1,93 @@
+use clap::{Parser, Subcommand};
+
+use crate::installer::plan::Consent;
+use crate::models::limits::{DownloadWindow, Rate};
+
+#[derive(Debug, Parser)]
+#[command(name = "youtube-downloader", version, about)]
+pub struct Cli {
+    /// Use the full-screen terminal interface instead of the numbered menu
+    #[arg(long)]
+    pub tui: bool,
+
+    /// Validate the language files and exit
+    #[arg(long)]
+    pub check_translations: bool,
+
+    /// Cap the total download speed for this run, e.g. 500K or 2M
+    #[arg(long, global = true, value_name = "RATE")]
+    pub limit_rate: Option<Rate>,
+
+    /// Only download between these times for this run, e.g. 22:00-06:00
+    #[arg(long, global = true, value_name = "START-END")]
+    pub window: Option<DownloadWindow>,
+
+    /// Ignore the speed limit and download window from the settings for this run
+    #[arg(long, global = true)]
+    pub no_limits: bool,
+
+    /// Install missing dependencies without asking
+    #[arg(long, short = 'y', global = true, conflicts_with_all = ["no_install", "dry_run_install"])]
+    pub yes: bool,
+
+    /// Never install missing dependencies, exit when one is needed
+    #[arg(long, global = true, conflicts_with = "dry_run_install")]
+    pub no_install: bool,
+
+    /// Print how missing dependencies would be installed and exit
+    #[arg(long, global = true)]
+    pub dry_run_install: bool,
+
+    #[command(subcommand)]
+    pub command: Option<CliCommand>,
+}
+
+impl Cli {
+    pub fn install_consent(&self) -> Consent {
+        if self.dry_run_install {
+            Consent::DryRun
+        } else if self.no_install {
+            Consent::No
+        } else if self.yes {
+            Consent::Yes
+        } else {
+            Consent::Ask
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum CliCommand {
+    /// Run in the background, downloading everything added to the queue
+    Daemon {
+        /// Also serve the HTTP API and web page, regardless of the config
+        #[arg(long)]
+        http: bool,
+    },
+    /// Check the dependencies and settings and print a diagnostics report
+    Doctor,
+    /// Send a command to a running daemon
+    Client {
+        #[command(subcommand)]
+        command: ClientCommand,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ClientCommand {
+    /// Add a video or playlist URL to the queue
+    Add { url: String },
+    /// List the queued items
+    List,
+    /// Show the running and finished downloads
+    Status,
+    /// Remove a queued item by its URL or position in the list, or cancel
+    /// a running download by its URL
+    Cancel { target: String },
+    /// Pause the running downloads and stop starting new ones
+    Pause,
+    /// Continue the paused downloads and the rest of the queue
+    Resume,
+    /// Shut the daemon down after the running downloads
+    Stop,
+}
@@ -0,0 +1,110 @@
+use std::io::BufReader;
+
+use interprocess::local_socket::{prelude::*, Stream};
+
+use crate::{
+    cli::ClientCommand,
+    daemon::protocol::{read_message, socket_name, write_message, Request, Response},
+    models::translation::Translations,
+};
+
+// Send one request to the running daemon and wait for its answer
+pub fn send(request: &Request) -> Result<Response, Box<dyn std::error::Error>> {
+    let mut stream = Stream::connect(socket_name()?)?;
+    write_message(&mut stream, request)?;
+    read_message(&mut BufReader::new(stream))
+}
+
+pub fn run(command: ClientCommand) -> Result<(), Box<dyn std::error::Error>> {
+    let request = match command {
+        ClientCommand::Add { url } => Request::Add { url },
+        ClientCommand::List => Request::List,
+        ClientCommand::Status => Request::Status,
+        ClientCommand::Cancel { target } => Request::Cancel { target },
+        ClientCommand::Pause => Request::Pause,
+        ClientCommand::Resume => Request::Resume,
+        ClientCommand::Stop => Request::Stop,
+    };
+
+    let response = match send(&request) {
+        Ok(response) => response,
+        Err(e) => {
+            return Err(Translations::t(
+                "daemon_not_running",
+                Some(&[("error", e.to_string().into())]),
+            )
+            .into())
+        }
+    };
+
+    match response {
+        Response::Added { videos } => {
+            for video in &videos {
+                let title = video.title.clone().unwrap_or_else(|| "Unknown".to_string());
+                println!(
+                    "{}",
+                    Translations::t("added_to_queue", Some(&[("title", title.into())]))
+                );
+            }
+            println!(
+                "{}",
+                Translations::t("daemon_added", Some(&[("count", videos.len().into())]))
+            );
+        }
+        Response::Queue { queue } => {
+            if queue.is_empty() {
+                println!("{}", Translations::t("download_queue_empty", None));
+            } else {
+                println!("{}", Translations::t("download_queue_title", None));
+                for (i, video) in queue.iter().enumerate() {
+                    let title = video.title.clone().unwrap_or_else(|| "Unknown".to_string());
+                    println!("{}. {}", i + 1, title);
+                }
+            }
+        }
+        Response::Status {
+            paused,
+            scheduled,
+            queued,
+            downloads,
+        } => {
+            let state_key = match (paused, scheduled) {
+                (true, _) => "daemon_state_paused",
+                (false, true) => "daemon_state_scheduled",
+                (false, false) => "daemon_state_running",
+            };
+            println!(
+                "{}",
+                Translations::t(
+                    "daemon_status",
+                    Some(&[
+                        ("state", Translations::t(state_key, None).into()),
+                        ("count", queued.into())
+                    ])
+                )
+            );
+            for download in &downloads {
+                let title = download
+                    .music
+                    .title
+                    .clone()
+                    .unwrap_or_else(|| "Unknown".to_string());
+                println!("  {} - {}", title, download.state.describe());
+                for warning in &download.warnings {
+                    println!("    {}", warning);
+                }
+            }
+        }
+        Response::Cancelled { video } => {
+            let title = video.title.clone().unwrap_or_else(|| "Unknown".to_string());
+            println!(
+                "{}",
+                Translations::t("daemon_cancelled", Some(&[("title", title.into())]))
+            );
+        }
+        Response::Done => println!("{}", Translations::t("daemon_done", None)),
+        Response::Error { message } => return Err(message.into()),
+    }
+
+    Ok(())
+}
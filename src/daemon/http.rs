@@ -0,0 +1,406 @@
+use std::{
+    io::{Read, Write},
+    net::SocketAddr,
+    thread,
+    time::Duration,
+};
+
+use percent_encoding::percent_decode_str;
+use serde::{Deserialize, Serialize};
+use tiny_http::{Header, Method, Request as HttpRequest, Response as HttpResponse, Server};
+
+use crate::{
+    app_config::{Config, HttpConfig},
+    daemon::{
+        protocol::{Request, Response},
+        Daemon, POLL_INTERVAL,
+    },
+    models::translation::Translations,
+    runtime::RuntimeTrait,
+};
+
+// Page for adding URLs and watching progress from a browser
+const INDEX_HTML: &str = include_str!("index.html");
+
+// How often the event stream checks for progress changes
+const EVENT_INTERVAL: Duration = Duration::from_millis(500);
+
+// Largest request body accepted, far more than a URL needs
+const MAX_BODY_SIZE: u64 = 64 * 1024;
+
+#[derive(Debug, Deserialize)]
+struct AddBody {
+    url: String,
+}
+
+#[derive(Debug, Serialize)]
+struct ErrorBody {
+    error: String,
+}
+
+// Serve the JSON API until the daemon stops
+pub(super) fn serve(daemon: Daemon, config: HttpConfig) -> Result<(), Box<dyn std::error::Error>> {
+    let server = Server::http(&config.bind_address).map_err(|e| e.to_string())?;
+    let bound = server
+        .server_addr()
+        .to_ip()
+        .ok_or("HTTP server isn't bound to an IP address")?;
+
+    println!(
+        "{}",
+        Translations::t(
+            "http_listening",
+            Some(&[("address", (&config.bind_address).into())])
+        )
+    );
+    if config.access_token.is_none() && !bound.ip().is_loopback() {
+        println!("{}", Translations::t("http_no_token", None));
+    }
+
+    thread::spawn(move || {
+        while daemon.runtime.start() {
+            match server.recv_timeout(POLL_INTERVAL) {
+                Ok(Some(request)) => {
+                    let daemon = daemon.clone();
+                    let token = config.access_token.clone();
+                    thread::spawn(move || handle(&daemon, request, bound, token.as_deref()));
+                }
+                Ok(None) => {}
+                Err(e) => println!("{}", e),
+            }
+        }
+    });
+
+    Ok(())
+}
+
+fn handle(daemon: &Daemon, mut request: HttpRequest, bound: SocketAddr, token: Option<&str>) {
+    let url = request.url().to_string();
+    let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+    let method = request.method().clone();
+
+    if !header_value(&request, "Host").is_some_and(|host| is_allowed_host(host, bound)) {
+        let _ = request.respond(json_response(
+            403,
+            &ErrorBody {
+                error: "Forbidden".to_string(),
+            },
+        ));
+        return;
+    }
+
+    // The page itself holds no data, the API calls it makes are checked
+    if method == Method::Get && path == "/" {
+        let response = HttpResponse::from_string(INDEX_HTML)
+            .with_header(header("Content-Type", "text/html; charset=utf-8"));
+        let _ = request.respond(response);
+        return;
+    }
+
+    // Other sites open in the browser may send requests here too. Refuse the
+    // ones the browser marks as coming from elsewhere, and POSTs that aren't
+    // JSON, since a page can only send those after a CORS preflight, which
+    // this server never answers.
+    let is_json = header_value(&request, "Content-Type")
+        .is_some_and(|value| value.starts_with("application/json"));
+    if !is_same_origin(&request) || (method == Method::Post && !is_json) {
+        let _ = request.respond(json_response(
+            403,
+            &ErrorBody {
+                error: "Forbidden".to_string(),
+            },
+        ));
+        return;
+    }
+
+    if !is_authorized(&request, query, token) {
+        let _ = request.respond(json_response(
+            401,
+            &ErrorBody {
+                error: "Unauthorized".to_string(),
+            },
+        ));
+        return;
+    }
+
+    if method == Method::Get && path == "/api/events" {
+        stream_events(daemon, request);
+        return;
+    }
+
+    let response = match (method, path) {
+        (Method::Get, "/api/queue") => protocol_response(daemon, Request::List),
+        (Method::Post, "/api/queue") => {
+            let mut body = Vec::new();
+            // One byte past the limit tells a body that is too large apart
+            match request
+                .as_reader()
+                .take(MAX_BODY_SIZE + 1)
+                .read_to_end(&mut body)
+            {
+                Ok(size) if size as u64 > MAX_BODY_SIZE => json_response(
+                    413,
+                    &ErrorBody {
+                        error: "Request body too large".to_string(),
+                    },
+                ),
+                Ok(_) => match serde_json::from_slice::<AddBody>(&body) {
+                    Ok(body) => protocol_response(daemon, Request::Add { url: body.url }),
+                    Err(e) => json_response(
+                        400,
+                        &ErrorBody {
+                            error: e.to_string(),
+                        },
+                    ),
+                },
+                Err(e) => json_response(
+                    400,
+                    &ErrorBody {
+                        error: e.to_string(),
+                    },
+                ),
+            }
+        }
+        (Method::Delete, path) if path.starts_with("/api/queue/") => {
+            // URLs are sent percent-encoded as a single path segment
+            let target = percent_decode_str(path.trim_start_matches("/api/queue/"))
+                .decode_utf8_lossy()
+                .into_owned();
+            protocol_response(daemon, Request::Cancel { target })
+        }
+        (Method::Get, "/api/status") => protocol_response(daemon, Request::Status),
+        (Method::Post, "/api/pause") => protocol_response(daemon, Request::Pause),
+        (Method::Post, "/api/resume") => protocol_response(daemon, Request::Resume),
+        (Method::Get, "/api/history") => {
+            json_response(200, &daemon.runtime.get_history().downloads)
+        }
+        (Method::Get, "/api/config") => {
+            // Never hand out the token, even to authorized clients
+            let mut config = Config::load();
+            config.http.access_token = config.http.access_token.map(|_| "***".to_string());
+            config.network = config.network.redacted();
+            json_response(200, &config)
+        }
+        _ => json_response(
+            404,
+            &ErrorBody {
+                error: "Not found".to_string(),
+            },
+        ),
+    };
+
+    let _ = request.respond(response);
+}
+
+fn is_authorized(request: &HttpRequest, query: &str, token: Option<&str>) -> bool {
+    let Some(token) = token else {
+        return true;
+    };
+
+    let bearer =
+        header_value(request, "Authorization").and_then(|value| value.strip_prefix("Bearer "));
+    // EventSource can't set headers, so the token may also come in the query
+    let query_token = query_param(query, "token");
+
+    bearer.is_some_and(|bearer| constant_time_eq(bearer, token))
+        || query_token.is_some_and(|query_token| constant_time_eq(&query_token, token))
+}
+
+fn header_value<'a>(request: &'a HttpRequest, name: &'static str) -> Option<&'a str> {
+    request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv(name))
+        .map(|h| h.value.as_str())
+}
+
+// A page on any domain can point that domain at this machine (DNS rebinding)
+// and then count as the same origin, so the Host must name this server: the
+// bound address or a loopback name, or any IP address when listening on all
+// interfaces. Rebinding needs a domain name, so IP addresses are safe.
+fn is_allowed_host(host: &str, bound: SocketAddr) -> bool {
+    // Browsers leave out the default port
+    let host = match host.rsplit_once(':') {
+        Some((_, port)) if port.parse::<u16>().is_ok() => host.to_string(),
+        _ => format!("{}:80", host),
+    };
+    if host.eq_ignore_ascii_case(&format!("localhost:{}", bound.port())) {
+        return true;
+    }
+    host.parse::<SocketAddr>().is_ok_and(|address| {
+        address.port() == bound.port()
+            && (address.ip().is_loopback()
+                || address.ip() == bound.ip()
+                || bound.ip().is_unspecified())
+    })
+}
+
+// Requests without an Origin don't come from a web page, e.g. curl
+fn is_same_origin(request: &HttpRequest) -> bool {
+    match header_value(request, "Origin") {
+        Some(origin) => {
+            header_value(request, "Host").is_some_and(|host| origin == format!("http://{}", host))
+        }
+        None => true,
+    }
+}
+
+// Decoded value of a query parameter
+fn query_param(query: &str, name: &str) -> Option<String> {
+    form_urlencoded::parse(query.as_bytes())
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.into_owned())
+}
+
+// Compare without stopping at the first difference, so the time taken doesn't
+// tell how much of a guessed token was right
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |difference, (a, b)| difference | (a ^ b))
+            == 0
+}
+
+fn protocol_response(daemon: &Daemon, request: Request) -> HttpResponse<std::io::Cursor<Vec<u8>>> {
+    let response = daemon.handle(request);
+    let status = match response {
+        Response::Error { .. } => 400,
+        _ => 200,
+    };
+    json_response(status, &response)
+}
+
+fn json_response<T: Serialize>(status: u16, body: &T) -> HttpResponse<std::io::Cursor<Vec<u8>>> {
+    let json = serde_json::to_string(body).unwrap_or_default();
+    HttpResponse::from_string(json)
+        .with_status_code(status)
+        .with_header(header("Content-Type", "application/json"))
+}
+
+fn header(name: &str, value: &str) -> Header {
+    Header::from_bytes(name.as_bytes(), value.as_bytes()).unwrap()
+}
+
+// Server-sent events with the daemon status whenever it changes. The response
+// is written by hand so every event is flushed right away.
+fn stream_events(daemon: &Daemon, request: HttpRequest) {
+    let mut writer = request.into_writer();
+    let head = "HTTP/1.1 200 OK\r\n\
+                Content-Type: text/event-stream\r\n\
+                Cache-Control: no-cache\r\n\
+                Connection: close\r\n\r\n";
+    if writer.write_all(head.as_bytes()).is_err() {
+        return;
+    }
+
+    let mut last = String::new();
+    while daemon.runtime.start() {
+        let status = serde_json::to_string(&daemon.handle(Request::Status)).unwrap_or_default();
+        if status != last {
+            let event = format!("data: {}\n\n", status);
+            if writer.write_all(event.as_bytes()).is_err() || writer.flush().is_err() {
+                // The client went away
+                return;
+            }
+            last = status;
+        }
+        thread::sleep(EVENT_INTERVAL);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::VecDeque,
+        net::TcpStream,
+        sync::{atomic::AtomicBool, Arc, Mutex},
+    };
+
+    use super::*;
+    use crate::{app_config::ConfigCache, models::limits::LimitOverrides, runtime::Runtime};
+
+    fn daemon() -> Daemon {
+        Daemon {
+            runtime: Runtime {
+                url_buffer: Arc::new(Mutex::new(VecDeque::new())),
+                downloads: Arc::default(),
+                history: Arc::default(),
+                state: Arc::new(AtomicBool::new(true)),
+                limit_overrides: LimitOverrides::default(),
+                config: ConfigCache::default(),
+            },
+            paused: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    // Send `head` (with `{port}` filled in) and `body` to a server handling a
+    // single request, and return the status line of the response
+    fn send(head: &str, body: &[u8]) -> String {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let bound = server.server_addr().to_ip().unwrap();
+        let mut stream = TcpStream::connect(bound).unwrap();
+        let head = head.replace("{port}", &bound.port().to_string());
+        stream
+            .write_all(format!("{}\r\n\r\n", head).as_bytes())
+            .unwrap();
+        stream.write_all(body).unwrap();
+
+        handle(&daemon(), server.recv().unwrap(), bound, None);
+
+        let mut response = String::new();
+        let _ = stream.read_to_string(&mut response);
+        response.lines().next().unwrap_or_default().to_string()
+    }
+
+    #[test]
+    fn requests_for_other_hosts_are_refused() {
+        let rebound = "GET /api/queue HTTP/1.1\r\n\
+                       Host: evil.example:{port}\r\n\
+                       Origin: http://evil.example:{port}\r\n\
+                       Connection: close";
+        assert!(send(rebound, b"").contains("403"));
+
+        let local = "GET /api/queue HTTP/1.1\r\nHost: localhost:{port}\r\nConnection: close";
+        assert!(send(local, b"").contains("200"));
+
+        let bound = "127.0.0.1:8080".parse().unwrap();
+        assert!(is_allowed_host("127.0.0.1:8080", bound));
+        assert!(is_allowed_host("[::1]:8080", bound));
+        assert!(!is_allowed_host("localhost.evil.com:8080", bound));
+        assert!(!is_allowed_host("192.168.1.5:8080", bound));
+        assert!(!is_allowed_host("localhost:8081", bound));
+
+        // Listening on all interfaces, reached by the machine's LAN address
+        let all = "0.0.0.0:8080".parse().unwrap();
+        assert!(is_allowed_host("192.168.1.5:8080", all));
+        assert!(!is_allowed_host("evil.example:8080", all));
+    }
+
+    #[test]
+    fn large_bodies_are_refused() {
+        let body = vec![b' '; MAX_BODY_SIZE as usize + 1];
+        let head = format!(
+            "POST /api/queue HTTP/1.1\r\n\
+             Host: 127.0.0.1:{{port}}\r\n\
+             Content-Type: application/json\r\n\
+             Content-Length: {}\r\n\
+             Connection: close",
+            body.len()
+        );
+        assert!(send(&head, &body).contains("413"));
+    }
+
+    #[test]
+    fn tokens_are_decoded_and_compared() {
+        let token = "a+b/c=d";
+        let query = "x=1&token=a%2Bb%2Fc%3Dd";
+        assert_eq!(query_param(query, "token").as_deref(), Some(token));
+        assert_eq!(query_param(query, "missing"), None);
+
+        assert!(constant_time_eq(token, "a+b/c=d"));
+        assert!(!constant_time_eq(token, "a+b/c=e"));
+        assert!(!constant_time_eq(token, "a+b/c="));
+        assert!(!constant_time_eq(token, ""));
+    }
+}
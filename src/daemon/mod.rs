@@ -0,0 +1,291 @@
+use std::{
+    io::{self, BufReader},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use interprocess::local_socket::{
+    prelude::*, Listener, ListenerNonblockingMode, ListenerOptions, Stream,
+};
+#[cfg(windows)]
+use interprocess::os::windows::local_socket::ListenerOptionsExt;
+
+use crate::{
+    app_config::Config,
+    daemon::protocol::{read_message_within, socket_name, write_message, Request, Response},
+    downloader::{download_video, fetch_playlist_videos, DownloadOptions, DownloadOutcome},
+    models::{
+        download::{Download, DownloadState, Interruption},
+        limits::Limits,
+        translation::Translations,
+    },
+    runtime::{Runtime, RuntimeTrait},
+    views::main::{print_download_state, print_download_warning},
+};
+
+pub mod client;
+mod http;
+pub mod protocol;
+
+// How long idle workers and the listener wait before checking again
+pub(super) const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+// How long a client may take to send its request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+// How often downloads finished by the workers are written to the history file
+const HISTORY_FLUSH_INTERVAL: Duration = Duration::from_secs(10);
+
+// State shared between the control socket and the download workers
+#[derive(Clone)]
+struct Daemon {
+    runtime: Runtime,
+    paused: Arc<AtomicBool>,
+}
+
+// Own the queue and download it with a pool of workers, while serving
+// requests from clients on the local control socket (and optionally HTTP)
+pub fn run(runtime: Runtime, force_http: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let listener = create_listener()?;
+
+    let daemon = Daemon {
+        runtime,
+        paused: Arc::new(AtomicBool::new(false)),
+    };
+
+    // Same parallelism as a batch download from the menu
+    let workers: Vec<_> = (0..rayon::current_num_threads())
+        .map(|_| {
+            let daemon = daemon.clone();
+            thread::spawn(move || daemon.work())
+        })
+        .collect();
+
+    println!("{}", Translations::t("daemon_listening", None));
+
+    let http_config = Config::load().http;
+    if force_http || http_config.enabled {
+        http::serve(daemon.clone(), http_config)?;
+    }
+
+    let mut last_flush = Instant::now();
+    while daemon.runtime.start() {
+        if last_flush.elapsed() >= HISTORY_FLUSH_INTERVAL {
+            daemon.runtime.flush_history();
+            last_flush = Instant::now();
+        }
+        match listener.accept() {
+            Ok(stream) => {
+                let daemon = daemon.clone();
+                thread::spawn(move || daemon.serve(stream));
+            }
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => thread::sleep(POLL_INTERVAL),
+            Err(e) => println!("{}", e),
+        }
+    }
+
+    for worker in workers {
+        let _ = worker.join();
+    }
+    daemon.runtime.flush_history();
+
+    println!("{}", Translations::t("daemon_stopped", None));
+    Ok(())
+}
+
+fn create_listener() -> Result<Listener, Box<dyn std::error::Error>> {
+    let create = || {
+        let options = ListenerOptions::new()
+            .name(socket_name()?)
+            .nonblocking(ListenerNonblockingMode::Accept);
+        #[cfg(windows)]
+        let options = options.security_descriptor(protocol::pipe_security()?);
+        options.create_sync()
+    };
+    match create() {
+        // Left behind by a daemon that didn't shut down cleanly
+        #[cfg(unix)]
+        Err(e)
+            if e.kind() == io::ErrorKind::AddrInUse && client::send(&Request::Status).is_err() =>
+        {
+            std::fs::remove_file(protocol::socket_path()?)?;
+            Ok(create()?)
+        }
+        Err(e) if e.kind() == io::ErrorKind::AddrInUse => {
+            Err(Translations::t("daemon_already_running", None).into())
+        }
+        listener => Ok(listener?),
+    }
+}
+
+fn is_window_open(limits: &Limits) -> bool {
+    limits.window.is_none_or(|window| window.is_open())
+}
+
+impl Daemon {
+    fn serve(&self, stream: Stream) {
+        let mut reader = BufReader::new(stream);
+        let response = match read_message_within::<Request>(&mut reader, REQUEST_TIMEOUT) {
+            Ok(request) => self.handle(request),
+            Err(e) => Response::Error {
+                message: e.to_string(),
+            },
+        };
+
+        if let Err(e) = write_message(reader.get_mut(), &response) {
+            println!("{}", e);
+        }
+    }
+
+    fn handle(&self, request: Request) -> Response {
+        match request {
+            Request::Add { url } => match fetch_playlist_videos(&url) {
+                Ok(videos) => {
+                    let mut buffer = self.runtime.get_url_buffer();
+                    let added: Vec<_> = videos
+                        .into_iter()
+                        .filter(|video| !buffer.contains(video))
+                        .collect();
+                    buffer.extend(added.iter().cloned());
+                    Response::Added { videos: added }
+                }
+                Err(e) => Response::Error {
+                    message: Translations::t(
+                        "error_fetching",
+                        Some(&[("error", e.to_string().into())]),
+                    ),
+                },
+            },
+            Request::List => Response::Queue {
+                queue: self.runtime.get_url_buffer().iter().cloned().collect(),
+            },
+            Request::Status => Response::Status {
+                paused: self.paused.load(Ordering::SeqCst),
+                scheduled: !is_window_open(&self.runtime.get_limits()),
+                queued: self.runtime.get_url_buffer().len(),
+                downloads: self.runtime.get_downloads().clone(),
+            },
+            Request::Cancel { target } => {
+                let mut buffer = self.runtime.get_url_buffer();
+                let position = match target.parse::<usize>() {
+                    Ok(number) => number.checked_sub(1).filter(|idx| *idx < buffer.len()),
+                    Err(_) => buffer.iter().position(|video| video.url == target),
+                };
+
+                if let Some(video) = position.and_then(|idx| buffer.remove(idx)) {
+                    return Response::Cancelled {
+                        video: Box::new(video),
+                    };
+                }
+                drop(buffer);
+
+                // Not queued, maybe it is being downloaded right now
+                let running = self
+                    .runtime
+                    .get_downloads()
+                    .iter()
+                    .rev()
+                    .find(|d| d.music.url == target && d.is_active())
+                    .map(|d| d.music.clone());
+                match running {
+                    Some(video)
+                        if self
+                            .runtime
+                            .interrupt_download(&video.url, Interruption::Cancelled) =>
+                    {
+                        Response::Cancelled {
+                            video: Box::new(video),
+                        }
+                    }
+                    _ => Response::Error {
+                        message: Translations::t(
+                            "daemon_not_found",
+                            Some(&[("target", target.into())]),
+                        ),
+                    },
+                }
+            }
+            Request::Pause => {
+                // Running downloads go back to the queue and continue on resume
+                self.paused.store(true, Ordering::SeqCst);
+                self.runtime.interrupt_downloads(Interruption::Paused);
+                Response::Done
+            }
+            Request::Resume => {
+                self.paused.store(false, Ordering::SeqCst);
+                Response::Done
+            }
+            Request::Stop => {
+                self.runtime.stop();
+                Response::Done
+            }
+        }
+    }
+
+    // Take items off the queue one at a time until the daemon stops
+    fn work(&self) {
+        while self.runtime.start() {
+            // Items stay queued while paused or outside the download window
+            let limits = self.runtime.get_limits();
+            let next = match self.paused.load(Ordering::SeqCst) || !is_window_open(&limits) {
+                true => None,
+                false => self.runtime.get_url_buffer().pop_front(),
+            };
+            let Some(video) = next else {
+                thread::sleep(POLL_INTERVAL);
+                continue;
+            };
+
+            let download = Download::new(video.clone());
+            let token = download.token.clone();
+            self.runtime.add_download(download);
+            let update = |state: DownloadState| {
+                print_download_state(&video, &state);
+                self.runtime.set_download_state(&video.url, state);
+            };
+            update(DownloadState::Downloading(0.0));
+
+            // Share the cap with the downloads running now and the ones other
+            // workers are about to take off the queue, like a batch download
+            let running = self
+                .runtime
+                .get_downloads()
+                .iter()
+                .filter(|d| d.is_active())
+                .count();
+            let workers =
+                (running + self.runtime.get_url_buffer().len()).min(rayon::current_num_threads());
+            // Reloaded for every item so settings changes apply without a restart
+            let result = DownloadOptions::new(&Config::load(), limits.rate_per_worker(workers))
+                .map_err(Into::into)
+                .and_then(|options| {
+                    download_video(&video, &options, &token, |percent| {
+                        self.runtime
+                            .set_download_state(&video.url, DownloadState::Downloading(percent))
+                    })
+                });
+
+            match result {
+                Ok(DownloadOutcome::Finished(duration, record, warnings)) => {
+                    for warning in &warnings {
+                        print_download_warning(&video, warning);
+                    }
+                    self.runtime.set_download_warnings(&video.url, warnings);
+                    update(DownloadState::Finished(duration.as_secs()));
+                    self.runtime.record_download(&record);
+                }
+                Ok(DownloadOutcome::Interrupted(interruption)) => {
+                    if interruption == Interruption::Paused {
+                        self.runtime.get_url_buffer().push_front(video.clone());
+                    }
+                    update(interruption.into());
+                }
+                Err(e) => update(DownloadState::Failed(e.to_string())),
+            }
+        }
+    }
+}
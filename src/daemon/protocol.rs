@@ -0,0 +1,147 @@
+use std::io::{self, BufRead, BufReader, Write};
+#[cfg(unix)]
+use std::{fs, path::PathBuf};
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+#[cfg(unix)]
+use interprocess::local_socket::GenericFilePath;
+#[cfg(not(unix))]
+use interprocess::local_socket::GenericNamespaced;
+use interprocess::local_socket::{prelude::*, Name, Stream};
+#[cfg(windows)]
+use interprocess::os::windows::security_descriptor::SecurityDescriptor;
+use serde::{Deserialize, Serialize};
+
+use crate::models::{download::Download, music::Music};
+
+// Every connection carries a single request line and a single response line,
+// both encoded as JSON
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "lowercase")]
+pub enum Request {
+    Add { url: String },
+    List,
+    Status,
+    // URL or 1-based position in the queue
+    Cancel { target: String },
+    Pause,
+    Resume,
+    Stop,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "response", rename_all = "lowercase")]
+pub enum Response {
+    Added {
+        videos: Vec<Music>,
+    },
+    Queue {
+        queue: Vec<Music>,
+    },
+    Status {
+        paused: bool,
+        // Waiting for the download window to open
+        scheduled: bool,
+        queued: usize,
+        downloads: Vec<Download>,
+    },
+    Cancelled {
+        video: Box<Music>,
+    },
+    Done,
+    Error {
+        message: String,
+    },
+}
+
+// Name of the control socket, only reachable by the user who started the
+// daemon: a socket file in a private directory on Unix, a named pipe with the
+// user name in it and an owner-only ACL (see `pipe_security`) on Windows
+#[cfg(unix)]
+pub fn socket_name() -> io::Result<Name<'static>> {
+    socket_path()?.to_fs_name::<GenericFilePath>()
+}
+
+#[cfg(not(unix))]
+pub fn socket_name() -> io::Result<Name<'static>> {
+    let user = std::env::var("USERNAME").unwrap_or_default();
+    format!("youtube-downloader-{}.sock", user).to_ns_name::<GenericNamespaced>()
+}
+
+// Named pipes are open to everyone on the machine by default. Only the pipe's
+// owner, the user who started the daemon, and the system get access.
+#[cfg(windows)]
+pub fn pipe_security() -> io::Result<SecurityDescriptor> {
+    SecurityDescriptor::deserialize(widestring::u16cstr!("D:P(A;;GA;;;OW)(A;;GA;;;SY)"))
+}
+
+// $XDG_RUNTIME_DIR belongs to the user alone, elsewhere a 0700 folder in the
+// data directory is used
+#[cfg(unix)]
+pub fn socket_path() -> io::Result<PathBuf> {
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+
+    if let Some(dir) = dirs::runtime_dir() {
+        return Ok(dir.join("youtube-downloader.sock"));
+    }
+    let dir = dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("youtube_downloader")
+        .join("run");
+    fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(&dir)?;
+    // Created by an older version, or with a different umask
+    fs::set_permissions(&dir, fs::Permissions::from_mode(0o700))?;
+    Ok(dir.join("daemon.sock"))
+}
+
+pub fn read_message<T: for<'de> Deserialize<'de>>(
+    reader: &mut BufReader<Stream>,
+) -> Result<T, Box<dyn std::error::Error>> {
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    Ok(serde_json::from_str(&line)?)
+}
+
+// Like `read_message`, but giving up when the line hasn't arrived within
+// `timeout`, so a client that connects and sends nothing doesn't hold on to a
+// thread of the daemon. Named pipes have no read timeouts, so the stream is
+// polled instead.
+pub fn read_message_within<T: for<'de> Deserialize<'de>>(
+    reader: &mut BufReader<Stream>,
+    timeout: Duration,
+) -> Result<T, Box<dyn std::error::Error>> {
+    let deadline = Instant::now() + timeout;
+    let mut line = Vec::new();
+    reader.get_ref().set_nonblocking(true)?;
+    loop {
+        match reader.read_until(b'\n', &mut line) {
+            Ok(_) => break,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock && Instant::now() < deadline => {
+                thread::sleep(Duration::from_millis(10));
+            }
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                return Err("Timed out waiting for the request".into());
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+    reader.get_ref().set_nonblocking(false)?;
+    Ok(serde_json::from_slice(&line)?)
+}
+
+pub fn write_message<T: Serialize>(
+    stream: &mut Stream,
+    message: &T,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut line = serde_json::to_string(message)?;
+    line.push('\n');
+    stream.write_all(line.as_bytes())?;
+    Ok(())
+}
@@ -1,21 +1,41 @@
-use std::time::Instant;
+use rayon::prelude::*;
+use std::fs;
+use std::io::{BufRead, BufReader, Read};
+use std::path::PathBuf;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
 use youtube_dl::{YoutubeDl, YoutubeDlOutput};
 
-use crate::installer::get_yt_dlp_path;
+use crate::app_config::{Config, NetworkConfig};
+use crate::installer::{get_ffmpeg_path, get_yt_dlp_path};
+use crate::metadata::{self, MetadataConfig};
+use crate::models::download::{CancellationToken, Download, DownloadState, Interruption};
+use crate::models::limits::{DownloadWindow, Rate};
 use crate::models::music::Music;
-use crate::models::translation::Translations;
+use crate::models::profile::{validate_extra_args, DownloadProfile, SubtitleFormat};
+use crate::postprocess::{self, tags::parse_title};
+use crate::runtime::RuntimeTrait;
 
-pub fn get_video_info(url: &str) -> Result<Music, Box<dyn std::error::Error>> {
-    println!("{}", Translations::t("fetching_video_info", Some(&[url])));
+const YT_DLP_MISSING: &str =
+    "yt-dlp was not found. Restart the application to install it, or run the doctor command.";
 
+// YoutubeDl with an explicit path and the configured network settings
+fn youtube_dl(url: &str, yt_dlp_path: String, network: &NetworkConfig) -> YoutubeDl {
     let mut youtube_dl = YoutubeDl::new(url);
-
-    let yt_dlp_path = get_yt_dlp_path().unwrap();
     youtube_dl.youtube_dl_path(yt_dlp_path);
-    // Create and run YoutubeDl with explicit path
     youtube_dl.socket_timeout("15");
+    for arg in network.args() {
+        youtube_dl.extra_arg(arg);
+    }
+    youtube_dl
+}
 
-    let output = youtube_dl.run()?;
+pub fn get_video_info(url: &str) -> Result<Music, Box<dyn std::error::Error>> {
+    let network = Config::load().network;
+    let output = youtube_dl(url, get_yt_dlp_path().ok_or(YT_DLP_MISSING)?, &network)
+        .run()
+        .map_err(|e| network.redact(&e.to_string()))?;
 
     let title = match output {
         YoutubeDlOutput::SingleVideo(video) => video.title,
@@ -26,56 +46,40 @@ pub fn get_video_info(url: &str) -> Result<Music, Box<dyn std::error::Error>> {
         url: url.to_string(),
         title,
         downloaded_at: None,
+        playlist: None,
+        playlist_index: None,
+        loudness: None,
+        metadata: None,
+        subtitles: Vec::new(),
     })
 }
 
+// The videos behind a link, one for a single video. Nothing is printed, so
+// it can run under the TUI as well.
 pub fn fetch_playlist_videos(url: &str) -> Result<Vec<Music>, Box<dyn std::error::Error>> {
-    // Check if yt-dlp is installed, if not try to install it
-    let yt_dlp_path = match get_yt_dlp_path() {
-        Some(path) => path,
-        None => {
-            println!("yt-dlp is not installed or not found. Attempting to install...");
-            crate::installer::install_yt_dlp()?;
-
-            // Verify installation was successful
-            match get_yt_dlp_path() {
-                Some(path) => {
-                    println!("yt-dlp installed successfully.");
-                    path
-                }
-                None => {
-                    return Err(
-                        "yt-dlp was not found after installation. Please install it manually."
-                            .into(),
-                    )
-                }
-            }
-        }
-    };
+    // Dependencies are only installed at startup, where the user can agree to it
+    let yt_dlp_path = get_yt_dlp_path().ok_or(YT_DLP_MISSING)?;
 
-    // Create and run YoutubeDl with explicit path
-    let mut youtube_dl = YoutubeDl::new(url);
-    youtube_dl.youtube_dl_path(yt_dlp_path);
-    youtube_dl.flat_playlist(true);
-    youtube_dl.socket_timeout("15");
-
-    let output = youtube_dl.run()?;
+    let network = Config::load().network;
+    let output = youtube_dl(url, yt_dlp_path, &network)
+        .flat_playlist(true)
+        .run()
+        .map_err(|e| network.redact(&e.to_string()))?;
 
     let mut videos = Vec::new();
 
     if let Some(playlist) = output.clone().into_playlist() {
-        println!(
-            "Found playlist: {}",
-            playlist.title.unwrap_or_else(|| "Unknown".to_string())
-        );
-
         if let Some(entries) = playlist.entries {
-            println!("Number of videos in playlist: {}", entries.len());
-            for video in entries {
+            for (idx, video) in entries.into_iter().enumerate() {
                 videos.push(Music {
                     url: video.url.unwrap_or_else(|| "Unknown".to_string()),
                     title: video.title,
                     downloaded_at: None,
+                    playlist: playlist.title.clone(),
+                    playlist_index: Some(idx as u32 + 1),
+                    loudness: None,
+                    metadata: None,
+                    subtitles: Vec::new(),
                 });
             }
         } else {
@@ -90,66 +94,463 @@ pub fn fetch_playlist_videos(url: &str) -> Result<Vec<Music>, Box<dyn std::error
     Ok(videos)
 }
 
-pub fn download_video(video: &Music, download_dir: &str) -> Result<(), Box<dyn std::error::Error>> {
-    let title = video.title.clone().unwrap_or_else(|| "Unknown".to_string());
-    println!("{}", Translations::t("video_downloading", Some(&[&title])));
+// How a download that didn't fail ended
+#[derive(Debug, Clone, PartialEq)]
+pub enum DownloadOutcome {
+    // With the video as it is recorded in the history, and the steps that
+    // failed without failing the download
+    Finished(Duration, Box<Music>, Vec<String>),
+    Interrupted(Interruption),
+}
+
+// Settings for a single yt-dlp run
+#[derive(Debug, Clone, Default)]
+pub struct DownloadOptions {
+    pub download_dir: String,
+    pub rate_limit: Option<Rate>,
+    pub network: NetworkConfig,
+    pub profile: DownloadProfile,
+    // Global extra arguments followed by the profile's
+    pub extra_args: Vec<String>,
+    pub metadata: Option<MetadataConfig>,
+}
+
+impl DownloadOptions {
+    // The config is loaded again for every download and may have been edited
+    // since it was validated at startup, so the extra arguments are checked here
+    pub fn new(config: &Config, rate_limit: Option<Rate>) -> Result<Self, String> {
+        let profile = config.active_profile();
+        let extra_args: Vec<String> = config
+            .extra_args
+            .iter()
+            .chain(&profile.extra_args)
+            .cloned()
+            .collect();
+        validate_extra_args(&extra_args)?;
+        Ok(Self {
+            download_dir: config.get_download_dir().to_string(),
+            rate_limit,
+            network: config.network.clone(),
+            extra_args,
+            profile,
+            metadata: config.metadata.clone(),
+        })
+    }
+
+    // Directory the video is saved in
+    pub fn target_dir(&self, video: &Music) -> PathBuf {
+        let dir = PathBuf::from(&self.download_dir);
+        match &video.playlist {
+            Some(playlist) if self.profile.playlist_folders => dir.join(folder_name(playlist)),
+            _ => dir,
+        }
+    }
+}
 
+pub fn download_video(
+    video: &Music,
+    options: &DownloadOptions,
+    token: &CancellationToken,
+    on_progress: impl Fn(f32) + Sync,
+) -> Result<DownloadOutcome, Box<dyn std::error::Error>> {
     let start_time = Instant::now();
+    let mut warnings = Vec::new();
+    let video = &metadata::enrich(
+        video,
+        options.metadata.as_ref(),
+        &options.profile.tags.title_patterns,
+        &options.network,
+        &mut warnings,
+    )?;
 
-    // Check if yt-dlp is installed, if not try to install it
-    let yt_dlp_path = match get_yt_dlp_path() {
-        Some(path) => path,
-        None => {
-            println!("yt-dlp is not installed or not found. Attempting to install...");
-            crate::installer::install_yt_dlp()?;
-
-            // Verify installation was successful
-            match get_yt_dlp_path() {
-                Some(path) => {
-                    println!("yt-dlp installed successfully.");
-                    path
+    // Dependencies are only installed at startup, where the user can agree to it
+    let yt_dlp_path = get_yt_dlp_path().ok_or(YT_DLP_MISSING)?;
+
+    // yt-dlp is spawned directly instead of through YoutubeDl so that its progress
+    // output can be followed and the process stopped while the download is running
+    let mut command = Command::new(yt_dlp_path);
+    command
+        .args([
+            "--extract-audio",
+            "--audio-format",
+            &options.profile.audio_format,
+        ])
+        .args(["--embed-metadata", "--embed-thumbnail"])
+        .arg("-o")
+        .arg(output_template(video, &options.profile))
+        .arg("-P")
+        .arg(options.target_dir(video))
+        .args(["-o", CHAPTER_TEMPLATE])
+        .args(["--newline", "--progress-template", PROGRESS_TEMPLATE])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    // ffmpeg outside of the PATH, e.g. the static build the application downloaded
+    if let Some(ffmpeg) = get_ffmpeg_path().filter(|path| path != "ffmpeg") {
+        command.args(["--ffmpeg-location", &ffmpeg]);
+    }
+    if let Some(Rate(bytes)) = options.rate_limit {
+        command.args(["--limit-rate", &bytes.to_string()]);
+    }
+    command.args(options.profile.args());
+    command.args(options.network.args());
+    command.args(&options.extra_args);
+    command.arg(&video.url);
+    // Its own process group keeps a Ctrl-C in the terminal from reaching yt-dlp,
+    // we decide ourselves whether the download is paused or cancelled
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut command, 0);
+    let mut child = command.spawn()?;
+
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+
+    let (status, interruption, files, stderr) = thread::scope(|scope| {
+        let stdout = scope.spawn(|| {
+            let mut files = OutputFiles::default();
+            for line in stdout
+                .into_iter()
+                .flat_map(|out| BufReader::new(out).lines().map_while(Result::ok))
+            {
+                match parse_progress(&line) {
+                    Some(percent) => on_progress(percent),
+                    None => files.parse_line(&line),
                 }
-                None => {
-                    return Err(
-                        "yt-dlp was not found after installation. Please install it manually."
-                            .into(),
-                    )
+            }
+            files
+        });
+        // Drained on its own thread so a chatty yt-dlp can't block on a full pipe
+        let stderr = scope.spawn(|| {
+            let mut output = String::new();
+            if let Some(mut stderr) = stderr {
+                let _ = stderr.read_to_string(&mut output);
+            }
+            output
+        });
+
+        let mut interruption = None;
+        let status = loop {
+            if let Some(status) = child.try_wait()? {
+                break status;
+            }
+            if interruption.is_none() {
+                interruption = token.interruption();
+                if interruption.is_some() {
+                    break terminate(&mut child)?;
                 }
             }
+            thread::sleep(POLL_INTERVAL);
+        };
+
+        let files = stdout.join().unwrap_or_default();
+        let stderr = stderr.join().unwrap_or_default();
+        Ok::<_, std::io::Error>((status, interruption, files, stderr))
+    })?;
+
+    match interruption {
+        Some(Interruption::Cancelled) => {
+            // Paused downloads keep their partial files so yt-dlp can continue them
+            for destination in files.destinations {
+                for suffix in ["part", "ytdl"] {
+                    let mut partial = destination.clone().into_os_string();
+                    partial.push(format!(".{}", suffix));
+                    let _ = fs::remove_file(partial);
+                }
+            }
+            Ok(DownloadOutcome::Interrupted(Interruption::Cancelled))
+        }
+        Some(interruption) => Ok(DownloadOutcome::Interrupted(interruption)),
+        None if status.success() => {
+            let mut record = postprocess::run(
+                video,
+                &options.profile,
+                files.audio.as_deref(),
+                &files.chapters,
+                &options.network,
+                &mut warnings,
+            )?;
+            record.subtitles = files.subtitle_files(options.profile.subtitles.format);
+            Ok(DownloadOutcome::Finished(
+                start_time.elapsed(),
+                Box::new(record),
+                warnings,
+            ))
+        }
+        None => {
+            let error = stderr
+                .lines()
+                .rfind(|line| line.starts_with("ERROR"))
+                .map(|line| line.to_string())
+                .unwrap_or_else(|| format!("yt-dlp exited with {}", status));
+            Err(options.network.redact(&error).into())
+        }
+    }
+}
+
+// Files yt-dlp reports writing, picked out of its output
+#[derive(Debug, Default)]
+struct OutputFiles {
+    // Everything downloaded, including partial files
+    destinations: Vec<PathBuf>,
+    // The converted audio file
+    audio: Option<PathBuf>,
+    // Tracks split from the audio file, in chapter order
+    chapters: Vec<PathBuf>,
+    // Subtitles as downloaded, before they are converted
+    subtitles: Vec<PathBuf>,
+}
+
+impl OutputFiles {
+    // Subtitle files left after converting them to `format`
+    fn subtitle_files(&self, format: Option<SubtitleFormat>) -> Vec<PathBuf> {
+        self.subtitles
+            .iter()
+            .map(|file| match format {
+                Some(format) => file.with_extension(format.extension()),
+                None => file.clone(),
+            })
+            .filter(|file| file.exists())
+            .collect()
+    }
+
+    fn parse_line(&mut self, line: &str) {
+        // Printed instead of a destination when the download already has the
+        // requested format
+        if let Some(rest) = line.strip_prefix("[ExtractAudio] Not converting audio ") {
+            if let Some((path, _)) = rest.rsplit_once("; ") {
+                self.audio = Some(PathBuf::from(path));
+            }
+            return;
+        }
+        if let Some((_, path)) = line.split_once("Writing video subtitles to: ") {
+            self.subtitles.push(PathBuf::from(path));
+            return;
         }
+
+        let Some((prefix, path)) = line.split_once("Destination: ") else {
+            return;
+        };
+        let path = PathBuf::from(path);
+
+        if prefix.starts_with("[ExtractAudio]") {
+            self.audio = Some(path);
+        } else if prefix.starts_with("[SplitChapters]") {
+            self.chapters.push(path);
+        } else {
+            self.destinations.push(path);
+        }
+    }
+}
+
+// yt-dlp output template of the audio file. The placeholders of the profile's
+// file name are filled in with the details known before downloading, the
+// others are left to yt-dlp.
+fn output_template(video: &Music, profile: &DownloadProfile) -> String {
+    let Some(filename) = &profile.filename else {
+        return "%(title)s.%(ext)s".to_string();
+    };
+    let info = video.metadata.clone().unwrap_or_default();
+    let parsed = video
+        .title
+        .as_deref()
+        .and_then(|title| parse_title(title, &profile.tags.title_patterns));
+    let (parsed_artist, parsed_title) = match parsed {
+        Some((artist, title)) => (artist, Some(title)),
+        None => (None, None),
     };
 
-    // Create and run YoutubeDl with explicit path
-    let mut youtube_dl = YoutubeDl::new(&video.url);
-    youtube_dl.youtube_dl_path(yt_dlp_path);
-    youtube_dl.extract_audio(true);
-    youtube_dl.extra_arg("--audio-format");
-    youtube_dl.extra_arg("mp3");
-    youtube_dl.extra_arg("--embed-metadata");
-    youtube_dl.extra_arg("--embed-thumbnail");
-    youtube_dl.output_template("%(title)s.%(ext)s");
-    let _ = youtube_dl.download_to(download_dir);
-
-    let status = youtube_dl.run();
-
-    match status {
-        Ok(_) => {
-            let duration = start_time.elapsed();
-            println!(
-                "{}",
-                Translations::t(
-                    "video_downloaded",
-                    Some(&[&title, &duration.as_secs().to_string()])
-                )
-            );
-            Ok(())
+    let values = [
+        (
+            "{artist}",
+            info.artist.or(parsed_artist),
+            "%(artist,uploader)s",
+        ),
+        ("{title}", info.title.or(parsed_title), "%(title)s"),
+        (
+            "{album}",
+            info.album.or_else(|| video.playlist.clone()),
+            "%(album|)s",
+        ),
+        (
+            "{track}",
+            info.track
+                .or(video.playlist_index)
+                .map(|track| format!("{:02}", track)),
+            "%(track_number|)s",
+        ),
+        (
+            "{year}",
+            info.year.map(|year| year.to_string()),
+            "%(release_year|)s",
+        ),
+    ];
+
+    // "%" starts a field in yt-dlp templates
+    let mut template = filename.replace('%', "%%");
+    for (placeholder, value, field) in values {
+        let value = value.map_or(field.to_string(), |value| {
+            folder_name(&value).replace('%', "%%")
+        });
+        template = template.replace(placeholder, &value);
+    }
+    format!("{}.%(ext)s", template)
+}
+
+// Replace the characters that aren't allowed in file names on some systems
+pub fn folder_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    // Windows drops trailing dots and spaces
+    match name.trim_end_matches(['.', ' ']) {
+        "" => "_".to_string(),
+        name => name.to_string(),
+    }
+}
+
+// How often a running download checks its cancellation token
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+// How long yt-dlp gets to clean up after being interrupted before it is killed
+const TERMINATE_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Ask yt-dlp to stop like a Ctrl-C would, so it closes its files and its
+// ffmpeg children, and kill it if it doesn't exit in time
+fn terminate(child: &mut Child) -> std::io::Result<ExitStatus> {
+    #[cfg(unix)]
+    {
+        // The negative pid signals the whole process group
+        unsafe { libc::kill(-(child.id() as libc::pid_t), libc::SIGINT) };
+
+        let deadline = Instant::now() + TERMINATE_TIMEOUT;
+        while Instant::now() < deadline {
+            if let Some(status) = child.try_wait()? {
+                return Ok(status);
+            }
+            thread::sleep(POLL_INTERVAL);
         }
-        Err(e) => {
-            println!(
-                "{}",
-                Translations::t("video_download_failed", Some(&[&title, &e.to_string()]))
-            );
-            Err(e.into())
+    }
+
+    child.kill()?;
+    child.wait()
+}
+
+// Block until the download window opens or the download is interrupted
+fn wait_for_window(window: &DownloadWindow, token: &CancellationToken) {
+    while !window.is_open() && token.interruption().is_none() {
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+// Tracks split by chapter go into a folder named after the video
+const CHAPTER_TEMPLATE: &str = "chapter:%(title)s/%(section_number)02d - %(section_title)s.%(ext)s";
+
+const PROGRESS_TEMPLATE: &str = "download:[progress] %(progress._percent_str)s";
+
+// Parse the percentage out of a line printed with `PROGRESS_TEMPLATE`
+fn parse_progress(line: &str) -> Option<f32> {
+    line.strip_prefix("[progress]")?
+        .trim()
+        .trim_end_matches('%')
+        .parse()
+        .ok()
+}
+
+// What happened to the videos of a batch
+#[derive(Debug, Default)]
+pub struct BatchSummary {
+    pub successful: Vec<Music>,
+    pub failed: usize,
+    // Put back at the front of the queue, in their original order
+    pub paused: usize,
+    pub cancelled: usize,
+}
+
+// Download every video in parallel, tracking their state on the runtime.
+// `report` is called on every state change. Finished videos are added to the
+// history as they complete and the file is written once at the end, paused
+// ones go back to the queue. The runtime's limits are checked before each
+// video starts: the speed cap is shared by the parallel workers and videos
+// wait for the download window to open.
+pub fn download_batch<R: RuntimeTrait + Sync>(
+    runtime: &R,
+    videos: Vec<Music>,
+    download_dir: &str,
+    report: impl Fn(&Music, &DownloadState) + Sync,
+) -> BatchSummary {
+    let downloads: Vec<Download> = videos.into_iter().map(Download::new).collect();
+    *runtime.get_downloads() = downloads.clone();
+
+    let update = |video: &Music, state: DownloadState| {
+        report(video, &state);
+        runtime.set_download_state(&video.url, state);
+    };
+
+    let workers = rayon::current_num_threads().min(downloads.len());
+    let results: Vec<(Music, DownloadState)> = downloads
+        .into_par_iter()
+        .map(|Download { music, token, .. }| {
+            let limits = runtime.get_limits();
+            if let Some(window) = limits.window.filter(|window| !window.is_open()) {
+                update(&music, DownloadState::Scheduled);
+                wait_for_window(&window, &token);
+            }
+            let options = DownloadOptions::new(&Config::load(), limits.rate_per_worker(workers))
+                .map(|options| DownloadOptions {
+                    download_dir: download_dir.to_string(),
+                    ..options
+                });
+
+            // Items still waiting when the batch is stopped are never started
+            let state = match token.interruption() {
+                Some(interruption) => interruption.into(),
+                None => {
+                    update(&music, DownloadState::Downloading(0.0));
+                    let result = options.map_err(Into::into).and_then(|options| {
+                        download_video(&music, &options, &token, |percent| {
+                            runtime
+                                .set_download_state(&music.url, DownloadState::Downloading(percent))
+                        })
+                    });
+                    match result {
+                        Ok(DownloadOutcome::Finished(duration, record, warnings)) => {
+                            runtime.record_download(&record);
+                            runtime.set_download_warnings(&music.url, warnings);
+                            DownloadState::Finished(duration.as_secs())
+                        }
+                        Ok(DownloadOutcome::Interrupted(interruption)) => interruption.into(),
+                        Err(e) => DownloadState::Failed(e.to_string()),
+                    }
+                }
+            };
+            update(&music, state.clone());
+            (music, state)
+        })
+        .collect();
+
+    // Once for the whole batch
+    runtime.flush_history();
+
+    let mut summary = BatchSummary::default();
+    let mut paused = Vec::new();
+    for (music, state) in results {
+        match state {
+            DownloadState::Finished(_) => summary.successful.push(music),
+            DownloadState::Paused => paused.push(music),
+            DownloadState::Cancelled => summary.cancelled += 1,
+            _ => summary.failed += 1,
         }
     }
+
+    summary.paused = paused.len();
+    let mut buffer = runtime.get_url_buffer();
+    for music in paused.into_iter().rev() {
+        buffer.push_front(music);
+    }
+
+    summary
 }
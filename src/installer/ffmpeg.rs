@@ -0,0 +1,208 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::managed;
+use crate::app_config::NetworkConfig;
+
+// Static builds are around a hundred megabytes
+const TIMEOUT: Duration = Duration::from_secs(900);
+
+// `ffmpeg` in config.toml
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FfmpegConfig {
+    // Download a static ffmpeg and ffprobe build instead of installing ffmpeg
+    // with the system package manager
+    pub managed: bool,
+    // Folder with the build archives and their "checksums.sha256"
+    pub build_url: String,
+    // Archive to download, the build for this platform when unset
+    pub archive: Option<String>,
+}
+
+impl Default for FfmpegConfig {
+    fn default() -> Self {
+        Self {
+            managed: false,
+            build_url: "https://github.com/BtbN/FFmpeg-Builds/releases/latest/download".to_string(),
+            archive: None,
+        }
+    }
+}
+
+impl FfmpegConfig {
+    pub fn archive(&self) -> Option<&str> {
+        self.archive.as_deref().or(default_archive())
+    }
+}
+
+// Static build for this platform, None when there is no official one
+fn default_archive() -> Option<&'static str> {
+    if cfg!(all(target_os = "windows", target_arch = "x86_64")) {
+        Some("ffmpeg-master-latest-win64-gpl.zip")
+    } else if cfg!(all(target_os = "linux", target_arch = "x86_64")) {
+        Some("ffmpeg-master-latest-linux64-gpl.tar.xz")
+    } else if cfg!(all(target_os = "linux", target_arch = "aarch64")) {
+        Some("ffmpeg-master-latest-linuxarm64-gpl.tar.xz")
+    } else {
+        None
+    }
+}
+
+fn executable(name: &str) -> String {
+    match cfg!(target_os = "windows") {
+        true => format!("{}.exe", name),
+        false => name.to_string(),
+    }
+}
+
+pub fn binary_path(dir: &Path) -> PathBuf {
+    dir.join(executable("ffmpeg"))
+}
+
+// The managed copy, when it is enabled and installed
+pub fn managed_path(config: &FfmpegConfig) -> Option<String> {
+    let path = binary_path(&managed::tools_dir());
+    (config.managed && path.exists()).then(|| path.to_string_lossy().to_string())
+}
+
+// Download the static build into the application's data directory
+pub fn install(
+    config: &FfmpegConfig,
+    network: &NetworkConfig,
+) -> Result<(), Box<dyn std::error::Error>> {
+    install_into(config, &managed::tools_dir(), network)
+}
+
+// Download the archive, check it against the published checksums and keep the
+// ffmpeg and ffprobe binaries from it in `dir`
+pub fn install_into(
+    config: &FfmpegConfig,
+    dir: &Path,
+    network: &NetworkConfig,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let archive = config.archive().ok_or(
+        "There is no static ffmpeg build for this platform, set `archive` in the [ffmpeg] settings",
+    )?;
+    let base = config.build_url.trim_end_matches('/');
+    let client = network.http_client(TIMEOUT)?;
+
+    let sums = client
+        .get(format!("{}/checksums.sha256", base))
+        .send()?
+        .error_for_status()?
+        .text()?;
+    let checksum = managed::find_checksum(&sums, archive)
+        .ok_or_else(|| format!("No checksum published for {}", archive))?;
+
+    let unpacked = dir.join("ffmpeg.unpacked");
+    let _ = fs::remove_dir_all(&unpacked);
+    fs::create_dir_all(&unpacked)?;
+    let archive_path = unpacked.join(archive);
+    managed::download_verified(
+        &client,
+        &format!("{}/{}", base, archive),
+        &checksum,
+        &archive_path,
+    )?;
+
+    let result = extract(&archive_path, &unpacked).and_then(|_| {
+        for name in ["ffmpeg", "ffprobe"] {
+            let name = executable(name);
+            let binary = find_file(&unpacked, &name)
+                .ok_or_else(|| format!("{} is missing from {}", name, archive))?;
+            fs::rename(binary, dir.join(&name))?;
+        }
+        Ok(())
+    });
+    let _ = fs::remove_dir_all(&unpacked);
+    result
+}
+
+// tar reads .tar.xz archives everywhere and .zip archives on Windows too
+fn extract(archive: &Path, dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let status = Command::new("tar")
+        .arg("-xf")
+        .arg(archive)
+        .arg("-C")
+        .arg(dir)
+        .status()?;
+    if !status.success() {
+        return Err(format!(
+            "Could not extract {}: tar exited with {}",
+            archive.display(),
+            status
+        )
+        .into());
+    }
+    Ok(())
+}
+
+fn find_file(dir: &Path, name: &str) -> Option<PathBuf> {
+    for entry in fs::read_dir(dir).ok()?.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            if let Some(found) = find_file(&path, name) {
+                return Some(found);
+            }
+        } else if path.file_name().is_some_and(|file| file == name) {
+            return Some(path);
+        }
+    }
+    None
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::test_http::serve_routes;
+
+    #[test]
+    fn static_build_is_downloaded_and_unpacked() {
+        let dir =
+            std::env::temp_dir().join(format!("youtube_downloader_ffmpeg_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let build = dir.join("build").join("ffmpeg-test").join("bin");
+        fs::create_dir_all(&build).unwrap();
+        fs::write(build.join("ffmpeg"), "ffmpeg").unwrap();
+        fs::write(build.join("ffprobe"), "ffprobe").unwrap();
+        let archive = dir.join("ffmpeg-test.tar.gz");
+        let status = Command::new("tar")
+            .arg("-czf")
+            .arg(&archive)
+            .arg("-C")
+            .arg(dir.join("build"))
+            .arg("ffmpeg-test")
+            .status()
+            .unwrap();
+        assert!(status.success());
+        let data = fs::read(&archive).unwrap();
+        let sums = format!("{}  ffmpeg-test.tar.gz\n", managed::sha256_hex(&data));
+
+        let url = serve_routes(vec![
+            ("/checksums.sha256".to_string(), sums.into_bytes()),
+            ("/ffmpeg-test.tar.gz".to_string(), data),
+        ]);
+
+        let config = FfmpegConfig {
+            managed: true,
+            build_url: url,
+            archive: Some("ffmpeg-test.tar.gz".to_string()),
+        };
+        let tools = dir.join("bin");
+        install_into(&config, &tools, &NetworkConfig::default()).unwrap();
+        assert_eq!(fs::read_to_string(binary_path(&tools)).unwrap(), "ffmpeg");
+        assert_eq!(
+            fs::read_to_string(tools.join("ffprobe")).unwrap(),
+            "ffprobe"
+        );
+        assert!(!tools.join("ffmpeg.unpacked").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,129 @@
+use std::{
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+// Folder of the tools the application downloads and updates itself
+pub fn tools_dir() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("youtube_downloader")
+        .join("bin")
+}
+
+// What was installed and when updates were last looked for, saved next to the
+// managed binary
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ManagedState {
+    pub version: String,
+    // Seconds since the Unix epoch
+    pub checked_at: u64,
+}
+
+impl ManagedState {
+    pub fn load(path: &Path) -> Option<Self> {
+        let content = fs::read_to_string(path).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    // Whether `interval` has passed since the last check. A zero interval
+    // turns the checks off.
+    pub fn is_check_due(&self, interval: Duration) -> bool {
+        !interval.is_zero() && now().saturating_sub(self.checked_at) >= interval.as_secs()
+    }
+}
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or_default()
+}
+
+// Checksum of `file_name` in a "SHA2-256SUMS" style list with one
+// "<hex digest>  <file name>" line per file
+pub fn find_checksum(sums: &str, file_name: &str) -> Option<String> {
+    sums.lines().find_map(|line| {
+        let (digest, name) = line.trim().split_once(char::is_whitespace)?;
+        // sha256sum marks binary files with a '*'
+        let name = name.trim_start().trim_start_matches('*');
+        (name == file_name).then(|| digest.to_lowercase())
+    })
+}
+
+pub fn sha256_hex(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+// Download `url` to `target`, replacing it only once the download is complete
+// and matches `sha256`. The file is made executable.
+pub fn download_verified(
+    client: &reqwest::blocking::Client,
+    url: &str,
+    sha256: &str,
+    target: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut data = Vec::new();
+    client
+        .get(url)
+        .send()?
+        .error_for_status()?
+        .read_to_end(&mut data)?;
+
+    let digest = sha256_hex(&data);
+    if !digest.eq_ignore_ascii_case(sha256) {
+        return Err(format!(
+            "Checksum mismatch for {}: expected {}, got {}",
+            url, sha256, digest
+        )
+        .into());
+    }
+
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let partial = target.with_extension("part");
+    fs::write(&partial, &data)?;
+    make_executable(&partial)?;
+    fs::rename(&partial, target)?;
+    Ok(())
+}
+
+#[cfg(unix)]
+fn make_executable(file: &Path) -> Result<(), std::io::Error> {
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(file, fs::Permissions::from_mode(0o755))
+}
+
+#[cfg(not(unix))]
+fn make_executable(_file: &Path) -> Result<(), std::io::Error> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checksums_are_found_by_file_name() {
+        let sums = "0a1b  yt-dlp\n2C3D  yt-dlp_linux\n4e5f *yt-dlp.exe\n";
+        assert_eq!(find_checksum(sums, "yt-dlp_linux").as_deref(), Some("2c3d"));
+        assert_eq!(find_checksum(sums, "yt-dlp.exe").as_deref(), Some("4e5f"));
+        assert_eq!(find_checksum(sums, "yt-dlp_macos"), None);
+
+        assert_eq!(
+            sha256_hex(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+}
@@ -0,0 +1,191 @@
+pub mod ffmpeg;
+pub mod managed;
+pub mod plan;
+pub mod yt_dlp;
+
+use std::path::Path;
+use std::process::Command;
+
+use crate::app_config::Config;
+
+#[cfg(target_os = "linux")]
+pub fn detect_linux_distro() -> Option<String> {
+    // Try reading /etc/os-release first (most modern distros)
+
+    use std::fs;
+    if let Ok(content) = fs::read_to_string("/etc/os-release") {
+        for line in content.lines() {
+            if let Some(id) = line.strip_prefix("ID=") {
+                return Some(id.trim_matches('"').to_lowercase());
+            }
+        }
+    }
+
+    // Try reading /etc/lsb-release (Ubuntu and some others)
+    if let Ok(content) = fs::read_to_string("/etc/lsb-release") {
+        for line in content.lines() {
+            if let Some(id) = line.strip_prefix("DISTRIB_ID=") {
+                return Some(id.trim_matches('"').to_lowercase());
+            }
+        }
+    }
+
+    // Check for specific files that indicate certain distributions
+    if Path::new("/etc/arch-release").exists() {
+        return Some("arch".to_string());
+    }
+
+    if Path::new("/etc/fedora-release").exists() {
+        return Some("fedora".to_string());
+    }
+
+    if Path::new("/etc/redhat-release").exists() {
+        return Some("rhel".to_string());
+    }
+
+    if Path::new("/etc/debian_version").exists() {
+        return Some("debian".to_string());
+    }
+
+    // Try using command line tools as a last resort
+    if Command::new("lsb_release")
+        .arg("-is")
+        .output()
+        .map(|output| {
+            String::from_utf8_lossy(&output.stdout)
+                .trim()
+                .to_lowercase()
+        })
+        .ok()
+        .filter(|s| !s.is_empty())
+        .is_some()
+    {
+        return Command::new("lsb_release")
+            .arg("-is")
+            .output()
+            .ok()
+            .and_then(|output| {
+                let dist = String::from_utf8_lossy(&output.stdout)
+                    .trim()
+                    .to_lowercase();
+                if !dist.is_empty() {
+                    Some(dist)
+                } else {
+                    None
+                }
+            });
+    }
+
+    None
+}
+
+pub fn check_ffmpeg() -> bool {
+    get_ffmpeg_path().is_some()
+}
+
+pub fn get_ffmpeg_path() -> Option<String> {
+    // The static build the application downloaded is preferred
+    if let Some(path) = ffmpeg::managed_path(&Config::load().ffmpeg) {
+        return Some(path);
+    }
+
+    if Command::new("ffmpeg").arg("-version").output().is_ok() {
+        return Some("ffmpeg".to_string());
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        for path in ["/usr/local/bin/ffmpeg", "/usr/bin/ffmpeg", "/bin/ffmpeg"] {
+            if Path::new(path).exists() {
+                return Some(path.to_string());
+            }
+        }
+    }
+
+    None
+}
+
+// Program to run ffmpeg with, for the post-processing done by the application
+pub fn ffmpeg_command() -> String {
+    get_ffmpeg_path().unwrap_or_else(|| "ffmpeg".to_string())
+}
+
+pub fn check_yt_dlp() -> bool {
+    if yt_dlp::managed_path(&Config::load().yt_dlp).is_some() {
+        return true;
+    }
+
+    // Check for yt-dlp in various possible locations
+    let check_default = Command::new("yt-dlp").arg("--version").output().is_ok();
+
+    if check_default {
+        return true;
+    }
+
+    // On some systems, it might be installed but not in PATH
+    #[cfg(target_os = "windows")]
+    {
+        let home_dir = dirs::home_dir();
+        if let Some(home) = home_dir {
+            let local_path = home
+                .join("AppData")
+                .join("Local")
+                .join("yt-dlp")
+                .join("yt-dlp.exe");
+            if local_path.exists() {
+                return true;
+            }
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        // Check in common Linux locations
+        for path in ["/usr/local/bin/yt-dlp", "/usr/bin/yt-dlp", "/bin/yt-dlp"] {
+            if Path::new(path).exists() {
+                return true;
+            }
+        }
+    }
+
+    false
+}
+
+pub fn get_yt_dlp_path() -> Option<String> {
+    // The copy the application keeps up to date is preferred
+    if let Some(path) = yt_dlp::managed_path(&Config::load().yt_dlp) {
+        return Some(path);
+    }
+
+    // Then try the command directly
+    if Command::new("yt-dlp").arg("--version").output().is_ok() {
+        return Some("yt-dlp".to_string());
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        // Check in common Linux locations
+        for path in ["/usr/local/bin/yt-dlp", "/usr/bin/yt-dlp", "/bin/yt-dlp"] {
+            if Path::new(path).exists() {
+                return Some(path.to_string());
+            }
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        let home_dir = dirs::home_dir();
+        if let Some(home) = home_dir {
+            let local_path = home
+                .join("AppData")
+                .join("Local")
+                .join("yt-dlp")
+                .join("yt-dlp.exe");
+            if local_path.exists() {
+                return Some(local_path.to_string_lossy().to_string());
+            }
+        }
+    }
+
+    None
+}
@@ -0,0 +1,479 @@
+use std::process::Command;
+
+use super::{check_ffmpeg, check_yt_dlp, ffmpeg, yt_dlp};
+use crate::app_config::Config;
+use crate::models::translation::Translations;
+use crate::utils::read_line;
+
+// Whether missing dependencies may be installed, from the command line flags
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Consent {
+    // Show the plan and ask before running it
+    Ask,
+    // --yes
+    Yes,
+    // --no-install
+    No,
+    // --dry-run-install, only show the plan
+    DryRun,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Dependency {
+    Ffmpeg,
+    YtDlp,
+}
+
+// What was found of a dependency on this system
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Status {
+    // Some copy can be run, managed or not
+    pub installed: bool,
+    // The copy downloaded by the application is there
+    pub managed: bool,
+}
+
+impl Dependency {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Dependency::Ffmpeg => "ffmpeg",
+            Dependency::YtDlp => "yt-dlp",
+        }
+    }
+
+    pub fn is_installed(&self) -> bool {
+        match self {
+            Dependency::Ffmpeg => check_ffmpeg(),
+            Dependency::YtDlp => check_yt_dlp(),
+        }
+    }
+
+    pub fn status(&self, config: &Config) -> Status {
+        Status {
+            installed: self.is_installed(),
+            managed: match self {
+                Dependency::Ffmpeg => ffmpeg::managed_path(&config.ffmpeg).is_some(),
+                Dependency::YtDlp => yt_dlp::managed_path(&config.yt_dlp).is_some(),
+            },
+        }
+    }
+
+    // A managed copy is asked for in the config and can be downloaded for
+    // this platform
+    fn wants_managed(&self, config: &Config) -> bool {
+        match self {
+            Dependency::Ffmpeg => config.ffmpeg.managed && config.ffmpeg.archive().is_some(),
+            Dependency::YtDlp => config.yt_dlp.managed,
+        }
+    }
+
+    // Only a missing dependency is asked about at startup. A working copy
+    // from the system is kept even when the config asks for a managed one,
+    // which is then only downloaded with --yes.
+    pub fn needs_install(&self, status: Status) -> bool {
+        !status.installed
+    }
+
+    // Works, but the managed copy asked for in the config isn't there yet
+    pub fn wants_upgrade(&self, config: &Config, status: Status) -> bool {
+        status.installed && !status.managed && self.wants_managed(config)
+    }
+
+    pub fn plan(&self, config: &Config, status: Status) -> InstallPlan {
+        let mut methods = Vec::new();
+        if self.wants_managed(config) {
+            methods.push(match self {
+                Dependency::Ffmpeg => InstallMethod::FfmpegBuild,
+                Dependency::YtDlp => InstallMethod::YtDlpRelease,
+            });
+        }
+        // The system package manager only when there is no copy to fall back to
+        if !status.installed {
+            methods.extend(
+                package_commands(self.name())
+                    .into_iter()
+                    .map(InstallMethod::Command),
+            );
+        }
+        InstallPlan {
+            dependency: *self,
+            methods,
+        }
+    }
+}
+
+// One way of installing a dependency
+#[derive(Debug, Clone, PartialEq)]
+pub enum InstallMethod {
+    // Program and its arguments
+    Command(Vec<String>),
+    // The application's own copy of yt-dlp, see `yt_dlp::update`
+    YtDlpRelease,
+    // The static ffmpeg build, see `ffmpeg::install`
+    FfmpegBuild,
+}
+
+impl InstallMethod {
+    pub fn describe(&self, config: &Config) -> String {
+        match self {
+            InstallMethod::Command(args) => command_line(args),
+            InstallMethod::YtDlpRelease => match &config.yt_dlp.version {
+                Some(version) => Translations::t(
+                    "install_download_yt_dlp",
+                    Some(&[
+                        ("version", version.clone().into()),
+                        ("url", config.yt_dlp.releases_url.clone().into()),
+                    ]),
+                ),
+                None => Translations::t(
+                    "install_download_yt_dlp_latest",
+                    Some(&[("url", config.yt_dlp.releases_url.clone().into())]),
+                ),
+            },
+            InstallMethod::FfmpegBuild => Translations::t(
+                "install_download_ffmpeg",
+                Some(&[
+                    (
+                        "archive",
+                        config
+                            .ffmpeg
+                            .archive()
+                            .unwrap_or_default()
+                            .to_string()
+                            .into(),
+                    ),
+                    ("url", config.ffmpeg.build_url.clone().into()),
+                ]),
+            ),
+        }
+    }
+
+    fn run(&self, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            InstallMethod::Command(args) => {
+                println!("$ {}", command_line(args));
+                let status = Command::new(&args[0]).args(&args[1..]).status()?;
+                if !status.success() {
+                    return Err(format!("{} exited with {}", command_line(args), status).into());
+                }
+                Ok(())
+            }
+            InstallMethod::YtDlpRelease => {
+                yt_dlp::update(&config.yt_dlp, &config.network, print_yt_dlp_download).map(|_| ())
+            }
+            InstallMethod::FfmpegBuild => {
+                println!(
+                    "{}",
+                    Translations::t(
+                        "ffmpeg_downloading",
+                        Some(&[(
+                            "archive",
+                            config
+                                .ffmpeg
+                                .archive()
+                                .unwrap_or_default()
+                                .to_string()
+                                .into()
+                        )])
+                    )
+                );
+                ffmpeg::install(&config.ffmpeg, &config.network)
+            }
+        }
+    }
+}
+
+// Ways to install a dependency, each tried when the one before it failed
+#[derive(Debug, Clone, PartialEq)]
+pub struct InstallPlan {
+    pub dependency: Dependency,
+    pub methods: Vec<InstallMethod>,
+}
+
+impl InstallPlan {
+    pub fn describe(&self, config: &Config) -> String {
+        let name = self.dependency.name();
+        let mut lines = vec![Translations::t(
+            "install_plan",
+            Some(&[("name", name.into())]),
+        )];
+        if self.methods.is_empty() {
+            lines.push(Translations::t(
+                "install_no_method",
+                Some(&[("name", name.into())]),
+            ));
+        }
+        for (i, method) in self.methods.iter().enumerate() {
+            let key = match i {
+                0 => "install_step",
+                _ => "install_step_fallback",
+            };
+            lines.push(Translations::t(
+                key,
+                Some(&[("step", method.describe(config).into())]),
+            ));
+        }
+        lines.join("\n")
+    }
+
+    // Run the methods until one works, then check that the dependency can
+    // really be found
+    pub fn run(&self, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+        let name = self.dependency.name();
+        let mut errors = Vec::new();
+        for method in &self.methods {
+            match method.run(config) {
+                Ok(()) => break,
+                Err(e) => {
+                    let error = config.network.redact(&e.to_string());
+                    println!(
+                        "{}",
+                        Translations::t(
+                            "install_step_failed",
+                            Some(&[("error", error.clone().into())])
+                        )
+                    );
+                    errors.push(error);
+                }
+            }
+        }
+
+        let status = self.dependency.status(config);
+        if self.dependency.needs_install(status) {
+            return Err(match errors.is_empty() {
+                true => format!("{} was not found after installing it", name),
+                false => format!("Could not install {}: {}", name, errors.join("; ")),
+            }
+            .into());
+        }
+        if self.dependency.wants_upgrade(config, status) {
+            // Only the managed copy failed, the one on the system still works
+            println!(
+                "{}",
+                Translations::t("install_using_existing", Some(&[("name", name.into())]))
+            );
+            return Ok(());
+        }
+        println!(
+            "{}",
+            Translations::t("install_finished", Some(&[("name", name.into())]))
+        );
+        Ok(())
+    }
+}
+
+// Install what is missing, as far as `consent` allows
+pub fn install_missing(
+    config: &Config,
+    consent: Consent,
+) -> Result<(), Box<dyn std::error::Error>> {
+    install_with(config, consent, |dependency| dependency.status(config))
+}
+
+fn install_with(
+    config: &Config,
+    consent: Consent,
+    status: impl Fn(Dependency) -> Status,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let plans: Vec<InstallPlan> = [Dependency::Ffmpeg, Dependency::YtDlp]
+        .into_iter()
+        .filter_map(|dependency| {
+            let status = status(dependency);
+            // --yes also replaces a working copy with the managed one
+            let wanted = dependency.needs_install(status)
+                || (consent == Consent::Yes && dependency.wants_upgrade(config, status));
+            wanted.then(|| dependency.plan(config, status))
+        })
+        .collect();
+    if plans.is_empty() {
+        if consent == Consent::DryRun {
+            println!("{}", Translations::t("install_nothing", None));
+        }
+        return Ok(());
+    }
+
+    for plan in &plans {
+        println!("{}", plan.describe(config));
+    }
+    let confirmed = match consent {
+        Consent::Yes => true,
+        Consent::No => false,
+        Consent::DryRun => {
+            println!("{}", Translations::t("install_dry_run", None));
+            return Ok(());
+        }
+        Consent::Ask => {
+            let answer = read_line(Translations::t("install_confirm", None)).to_lowercase();
+            answer == "y" || answer == Translations::t("install_yes", None).to_lowercase()
+        }
+    };
+
+    if !confirmed {
+        println!("{}", Translations::t("install_declined", None));
+        return Err(format!(
+            "{} is not installed. Install it yourself, or allow the application to install it.",
+            plans[0].dependency.name()
+        )
+        .into());
+    }
+
+    for plan in &plans {
+        plan.run(config)?;
+    }
+    Ok(())
+}
+
+// Passed to `yt_dlp::update`, which doesn't print itself
+pub fn print_yt_dlp_download(version: &str) {
+    println!(
+        "{}",
+        Translations::t("yt_dlp_downloading", Some(&[("version", version.into())]))
+    );
+}
+
+// Arguments joined for display, quoting the ones with spaces
+fn command_line(args: &[String]) -> String {
+    args.iter()
+        .map(|arg| match arg.contains(' ') {
+            true => format!("\"{}\"", arg),
+            false => arg.clone(),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn command(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+// Package manager commands that install `package`, tried in order
+#[cfg(target_os = "linux")]
+fn package_commands(package: &str) -> Vec<Vec<String>> {
+    match super::detect_linux_distro().as_deref() {
+        Some("arch" | "manjaro" | "endeavouros") => {
+            vec![command(&["sudo", "pacman", "-S", "--noconfirm", package])]
+        }
+        Some("ubuntu" | "debian" | "linuxmint" | "pop") => {
+            vec![command(&["sudo", "apt", "install", "-y", package])]
+        }
+        Some("fedora" | "rhel" | "centos" | "rocky" | "alma") => vec![
+            command(&["sudo", "dnf", "install", "-y", package]),
+            command(&["sudo", "yum", "install", "-y", package]),
+        ],
+        Some("opensuse" | "opensuse-leap" | "opensuse-tumbleweed" | "suse") => {
+            vec![command(&["sudo", "zypper", "install", "-y", package])]
+        }
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn package_commands(package: &str) -> Vec<Vec<String>> {
+    vec![command(&["winget", "install", package])]
+}
+
+#[cfg(target_os = "macos")]
+fn package_commands(package: &str) -> Vec<Vec<String>> {
+    vec![command(&["brew", "install", package])]
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+fn package_commands(_package: &str) -> Vec<Vec<String>> {
+    Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MISSING: Status = Status {
+        installed: false,
+        managed: false,
+    };
+    const ON_PATH: Status = Status {
+        installed: true,
+        managed: false,
+    };
+    const MANAGED: Status = Status {
+        installed: true,
+        managed: true,
+    };
+
+    #[test]
+    fn only_missing_dependencies_are_installed() {
+        let config = Config::default();
+        assert!(config.yt_dlp.managed);
+
+        let yt_dlp = Dependency::YtDlp;
+        assert!(yt_dlp.needs_install(MISSING));
+        assert!(!yt_dlp.needs_install(ON_PATH));
+        assert!(!yt_dlp.needs_install(MANAGED));
+        assert!(yt_dlp.wants_upgrade(&config, ON_PATH));
+        assert!(!yt_dlp.wants_upgrade(&config, MANAGED));
+
+        let mut unmanaged = Config::default();
+        unmanaged.yt_dlp.managed = false;
+        assert!(!yt_dlp.wants_upgrade(&unmanaged, ON_PATH));
+
+        // The managed copy first, the package manager only when nothing works
+        let plan = yt_dlp.plan(&config, MISSING);
+        assert_eq!(plan.methods[0], InstallMethod::YtDlpRelease);
+        assert_eq!(plan.methods.len(), 1 + package_commands("yt-dlp").len());
+        assert_eq!(
+            yt_dlp.plan(&config, ON_PATH).methods,
+            [InstallMethod::YtDlpRelease]
+        );
+        assert!(yt_dlp
+            .plan(&unmanaged, MISSING)
+            .methods
+            .iter()
+            .all(|method| matches!(method, InstallMethod::Command(_))));
+    }
+
+    #[test]
+    fn declined_or_dry_run_plans_install_nothing() {
+        let config = Config::default();
+        let yt_dlp_missing = |dependency| match dependency {
+            Dependency::YtDlp => MISSING,
+            Dependency::Ffmpeg => ON_PATH,
+        };
+
+        // Nothing is missing, the managed copies aren't asked for
+        assert!(install_with(&config, Consent::No, |_| ON_PATH).is_ok());
+        assert!(install_with(&config, Consent::Ask, |_| ON_PATH).is_ok());
+
+        let error = install_with(&config, Consent::No, yt_dlp_missing).unwrap_err();
+        assert!(error.to_string().starts_with("yt-dlp "), "{}", error);
+
+        assert!(install_with(&config, Consent::DryRun, yt_dlp_missing).is_ok());
+        assert!(install_with(&config, Consent::DryRun, |_| MANAGED).is_ok());
+    }
+
+    #[test]
+    fn plans_list_every_method_in_order() {
+        let mut config = Config::default();
+        config.yt_dlp.version = Some("2025.01.15".to_string());
+        let plan = InstallPlan {
+            dependency: Dependency::YtDlp,
+            methods: vec![
+                InstallMethod::YtDlpRelease,
+                InstallMethod::Command(command(&["sudo", "apt", "install", "-y", "yt-dlp"])),
+            ],
+        };
+
+        let description = plan.describe(&config);
+        let lines: Vec<&str> = description.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].contains("2025.01.15"), "{}", description);
+        assert!(
+            lines[2].ends_with("sudo apt install -y yt-dlp"),
+            "{}",
+            description
+        );
+
+        assert_eq!(
+            command_line(&command(&["winget", "install", "Some Package"])),
+            "winget install \"Some Package\""
+        );
+    }
+}
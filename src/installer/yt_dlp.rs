@@ -0,0 +1,321 @@
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::managed::{self, ManagedState};
+use crate::app_config::NetworkConfig;
+
+// Release binaries are tens of megabytes
+const TIMEOUT: Duration = Duration::from_secs(300);
+
+// `yt_dlp` in config.toml. Distribution packages of yt-dlp are often too old
+// for YouTube, so by default the application keeps its own copy of the
+// official release binary up to date.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct YtDlpConfig {
+    // Use a yt-dlp downloaded by the application instead of the one on the PATH
+    pub managed: bool,
+    // Release tag to install, e.g. "2025.01.15". The latest release is used
+    // when unset.
+    pub version: Option<String>,
+    // Hours between checks for a new release, 0 turns them off
+    pub update_interval_hours: u64,
+    // Release downloads, "<releases_url>/download/<tag>/<file>"
+    pub releases_url: String,
+    // GitHub API of the repository, asked for the latest release
+    pub api_url: String,
+}
+
+impl Default for YtDlpConfig {
+    fn default() -> Self {
+        Self {
+            managed: true,
+            version: None,
+            update_interval_hours: 24,
+            releases_url: "https://github.com/yt-dlp/yt-dlp/releases".to_string(),
+            api_url: "https://api.github.com/repos/yt-dlp/yt-dlp".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Release {
+    tag_name: String,
+}
+
+// Name of the release file that runs on this platform
+pub fn asset_name() -> &'static str {
+    if cfg!(target_os = "windows") {
+        "yt-dlp.exe"
+    } else if cfg!(target_os = "macos") {
+        "yt-dlp_macos"
+    } else if cfg!(all(target_os = "linux", target_arch = "x86_64")) {
+        "yt-dlp_linux"
+    } else if cfg!(all(target_os = "linux", target_arch = "aarch64")) {
+        "yt-dlp_linux_aarch64"
+    } else if cfg!(all(target_os = "linux", target_arch = "arm")) {
+        "yt-dlp_linux_armv7l"
+    } else {
+        // Needs Python, but runs everywhere else
+        "yt-dlp"
+    }
+}
+
+pub fn binary_path(dir: &Path) -> PathBuf {
+    match cfg!(target_os = "windows") {
+        true => dir.join("yt-dlp.exe"),
+        false => dir.join("yt-dlp"),
+    }
+}
+
+// The managed copy, when it is enabled and installed
+pub fn managed_path(config: &YtDlpConfig) -> Option<String> {
+    let path = binary_path(&managed::tools_dir());
+    (config.managed && path.exists()).then(|| path.to_string_lossy().to_string())
+}
+
+// Install the managed copy when it is missing, or switch to the pinned version
+// or a new release. Returns the version installed, None when nothing changed.
+// `on_download` is told the version before it is downloaded.
+pub fn update(
+    config: &YtDlpConfig,
+    network: &NetworkConfig,
+    on_download: impl Fn(&str),
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    ManagedYtDlp::new(config, &managed::tools_dir(), network)?.update(on_download)
+}
+
+pub struct ManagedYtDlp<'a> {
+    config: &'a YtDlpConfig,
+    dir: PathBuf,
+    client: reqwest::blocking::Client,
+}
+
+impl<'a> ManagedYtDlp<'a> {
+    pub fn new(
+        config: &'a YtDlpConfig,
+        dir: &Path,
+        network: &NetworkConfig,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self {
+            config,
+            dir: dir.to_path_buf(),
+            client: network.http_client(TIMEOUT)?,
+        })
+    }
+
+    fn state_path(&self) -> PathBuf {
+        self.dir.join("yt-dlp.json")
+    }
+
+    // State of the installed copy, None when there is none
+    pub fn installed(&self) -> Option<ManagedState> {
+        match binary_path(&self.dir).exists() {
+            true => ManagedState::load(&self.state_path()),
+            false => None,
+        }
+    }
+
+    pub fn latest_version(&self) -> Result<String, Box<dyn std::error::Error>> {
+        let release: Release = self
+            .client
+            .get(format!(
+                "{}/releases/latest",
+                self.config.api_url.trim_end_matches('/')
+            ))
+            .send()?
+            .error_for_status()?
+            .json()?;
+        Ok(release.tag_name)
+    }
+
+    // Download `version` and check it against the release's checksums
+    pub fn install(&self, version: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let base = format!(
+            "{}/download/{}",
+            self.config.releases_url.trim_end_matches('/'),
+            version
+        );
+        let sums = self
+            .client
+            .get(format!("{}/SHA2-256SUMS", base))
+            .send()?
+            .error_for_status()?
+            .text()?;
+        let checksum = managed::find_checksum(&sums, asset_name())
+            .ok_or_else(|| format!("yt-dlp {} has no checksum for {}", version, asset_name()))?;
+
+        managed::download_verified(
+            &self.client,
+            &format!("{}/{}", base, asset_name()),
+            &checksum,
+            &binary_path(&self.dir),
+        )?;
+        ManagedState {
+            version: version.to_string(),
+            checked_at: managed::now(),
+        }
+        .save(&self.state_path())
+    }
+
+    pub fn update(
+        &self,
+        on_download: impl Fn(&str),
+    ) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        let installed = self.installed();
+
+        if let Some(pinned) = &self.config.version {
+            if installed.is_some_and(|state| &state.version == pinned) {
+                return Ok(None);
+            }
+            on_download(pinned);
+            self.install(pinned)?;
+            return Ok(Some(pinned.clone()));
+        }
+
+        let interval = Duration::from_secs(self.config.update_interval_hours * 60 * 60);
+        match installed {
+            Some(state) if !state.is_check_due(interval) => Ok(None),
+            Some(state) => {
+                let latest = self.latest_version()?;
+                if latest == state.version {
+                    ManagedState {
+                        checked_at: managed::now(),
+                        ..state
+                    }
+                    .save(&self.state_path())?;
+                    return Ok(None);
+                }
+                on_download(&latest);
+                self.install(&latest)?;
+                Ok(Some(latest))
+            }
+            None => {
+                let latest = self.latest_version()?;
+                on_download(&latest);
+                self.install(&latest)?;
+                Ok(Some(latest))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_http::serve_routes;
+
+    fn release(version: &str, binary: &[u8], checksum: &str) -> Vec<(String, Vec<u8>)> {
+        let base = format!("/releases/download/{}", version);
+        vec![
+            (
+                format!("{}/SHA2-256SUMS", base),
+                format!("{}  {}\n", checksum, asset_name()).into_bytes(),
+            ),
+            (format!("{}/{}", base, asset_name()), binary.to_vec()),
+        ]
+    }
+
+    fn config(url: &str) -> YtDlpConfig {
+        YtDlpConfig {
+            releases_url: format!("{}/releases", url),
+            api_url: format!("{}/api", url),
+            ..YtDlpConfig::default()
+        }
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "youtube_downloader_yt_dlp_{}_{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn latest_release_is_installed_and_checked_for_updates() {
+        let binary = b"#!/bin/sh\necho 2025.02.01\n";
+        let mut routes = release("2025.02.01", binary, &managed::sha256_hex(binary));
+        routes.push((
+            "/api/releases/latest".to_string(),
+            br#"{"tag_name": "2025.02.01"}"#.to_vec(),
+        ));
+        let config = config(&serve_routes(routes));
+        let dir = temp_dir("latest");
+        let managed = ManagedYtDlp::new(&config, &dir, &NetworkConfig::default()).unwrap();
+
+        assert_eq!(
+            managed.update(|_| {}).unwrap().as_deref(),
+            Some("2025.02.01")
+        );
+        assert_eq!(std::fs::read(binary_path(&dir)).unwrap(), binary);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(binary_path(&dir))
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o111, 0o111);
+        }
+
+        // Checked just now, and the latest release once the interval passed
+        assert_eq!(managed.update(|_| {}).unwrap(), None);
+        let mut state = managed.installed().unwrap();
+        state.checked_at = 0;
+        state.save(&managed.state_path()).unwrap();
+        assert_eq!(managed.update(|_| {}).unwrap(), None);
+        assert!(managed.installed().unwrap().checked_at > 0);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn pinned_version_replaces_the_installed_one() {
+        let binary = b"pinned";
+        let url = serve_routes(release("2024.12.23", binary, &managed::sha256_hex(binary)));
+        let config = YtDlpConfig {
+            version: Some("2024.12.23".to_string()),
+            ..config(&url)
+        };
+        let dir = temp_dir("pinned");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(binary_path(&dir), "newer").unwrap();
+        ManagedState {
+            version: "2025.02.01".to_string(),
+            checked_at: managed::now(),
+        }
+        .save(&dir.join("yt-dlp.json"))
+        .unwrap();
+
+        let managed = ManagedYtDlp::new(&config, &dir, &NetworkConfig::default()).unwrap();
+        assert_eq!(
+            managed.update(|_| {}).unwrap().as_deref(),
+            Some("2024.12.23")
+        );
+        assert_eq!(std::fs::read(binary_path(&dir)).unwrap(), binary);
+        assert_eq!(managed.update(|_| {}).unwrap(), None);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn downloads_with_the_wrong_checksum_are_rejected() {
+        let url = serve_routes(release("2025.02.01", b"tampered", &"0".repeat(64)));
+        let config = YtDlpConfig {
+            version: Some("2025.02.01".to_string()),
+            ..config(&url)
+        };
+        let dir = temp_dir("checksum");
+
+        let managed = ManagedYtDlp::new(&config, &dir, &NetworkConfig::default()).unwrap();
+        let error = managed.update(|_| {}).unwrap_err().to_string();
+        assert!(error.contains("Checksum mismatch"), "{}", error);
+        assert!(!binary_path(&dir).exists());
+        assert_eq!(managed.installed(), None);
+    }
+}
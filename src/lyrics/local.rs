@@ -0,0 +1,91 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use super::{Lyrics, LyricsProvider, LyricsQuery};
+use crate::downloader::folder_name;
+
+// Lyrics kept in a folder, in files named like "Artist - Title.lrc" for synced
+// and "Artist - Title.txt" for plain lyrics. Files named only after the title
+// are used when the artist is unknown.
+pub struct LocalProvider {
+    folder: PathBuf,
+}
+
+impl LocalProvider {
+    pub fn new(folder: &Path) -> Self {
+        Self {
+            folder: folder.to_path_buf(),
+        }
+    }
+
+    // The name comes from the tags, so separators and ".." can't lead out of
+    // the folder
+    fn read(&self, name: &str, extension: &str) -> Result<Option<String>, std::io::Error> {
+        let file = format!("{}.{}", folder_name(name), extension);
+        match fs::read_to_string(self.folder.join(file)) {
+            Ok(text) => Ok(Some(text)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl LyricsProvider for LocalProvider {
+    fn fetch(&self, query: &LyricsQuery) -> Result<Option<Lyrics>, Box<dyn std::error::Error>> {
+        let name = match &query.artist {
+            Some(artist) => format!("{} - {}", artist, query.title),
+            None => query.title.clone(),
+        };
+
+        let lyrics = Lyrics {
+            synced: self.read(&name, "lrc")?,
+            plain: self.read(&name, "txt")?,
+        };
+        match lyrics.synced.is_some() || lyrics.plain.is_some() {
+            true => Ok(Some(lyrics)),
+            false => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lyrics_are_read_by_artist_and_title() {
+        let folder =
+            std::env::temp_dir().join(format!("youtube_downloader_lyrics_{}", std::process::id()));
+        fs::create_dir_all(&folder).unwrap();
+        fs::write(folder.join("Daft Punk - Get Lucky.lrc"), "[00:01.00]Like").unwrap();
+        fs::write(folder.join("Intro.txt"), "Hello").unwrap();
+        let provider = LocalProvider::new(&folder);
+
+        let query = |artist: Option<&str>, title: &str| LyricsQuery {
+            artist: artist.map(str::to_string),
+            title: title.to_string(),
+            album: None,
+        };
+        let found = provider
+            .fetch(&query(Some("Daft Punk"), "Get Lucky"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(found.synced.as_deref(), Some("[00:01.00]Like"));
+        assert_eq!(found.plain, None);
+
+        let found = provider.fetch(&query(None, "Intro")).unwrap().unwrap();
+        assert_eq!(found.plain.as_deref(), Some("Hello"));
+
+        assert_eq!(provider.fetch(&query(None, "Missing")).unwrap(), None);
+
+        // Names from the tags stay inside the folder
+        fs::write(folder.with_extension("txt"), "Outside").unwrap();
+        let outside = format!("../{}", folder.file_name().unwrap().to_string_lossy());
+        assert_eq!(provider.fetch(&query(None, &outside)).unwrap(), None);
+        assert_eq!(provider.fetch(&query(Some(".."), "..")).unwrap(), None);
+        fs::remove_file(folder.with_extension("txt")).unwrap();
+        fs::remove_dir_all(folder).unwrap();
+    }
+}
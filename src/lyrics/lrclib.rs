@@ -0,0 +1,121 @@
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use super::{Lyrics, LyricsProvider, LyricsQuery};
+use crate::app_config::NetworkConfig;
+
+pub fn default_url() -> String {
+    "https://lrclib.net".to_string()
+}
+
+const TIMEOUT: Duration = Duration::from_secs(15);
+
+// Searches the LRCLIB lyrics database
+pub struct LrclibProvider {
+    url: String,
+    client: reqwest::blocking::Client,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Record {
+    #[serde(default)]
+    instrumental: bool,
+    plain_lyrics: Option<String>,
+    synced_lyrics: Option<String>,
+}
+
+impl LrclibProvider {
+    pub fn new(url: &str, network: &NetworkConfig) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self {
+            url: url.trim_end_matches('/').to_string(),
+            client: network.http_client(TIMEOUT)?,
+        })
+    }
+}
+
+impl LyricsProvider for LrclibProvider {
+    fn fetch(&self, query: &LyricsQuery) -> Result<Option<Lyrics>, Box<dyn std::error::Error>> {
+        let mut params = vec![("track_name", query.title.as_str())];
+        if let Some(artist) = &query.artist {
+            params.push(("artist_name", artist));
+        }
+        if let Some(album) = &query.album {
+            params.push(("album_name", album));
+        }
+
+        let records: Vec<Record> = self
+            .client
+            .get(format!("{}/api/search", self.url))
+            .query(&params)
+            .send()?
+            .error_for_status()?
+            .json()?;
+
+        // Synced lyrics are preferred over the best match with plain ones
+        let with_lyrics = |record: &&Record| {
+            !record.instrumental
+                && (record.synced_lyrics.is_some() || record.plain_lyrics.is_some())
+        };
+        let record = records
+            .iter()
+            .filter(with_lyrics)
+            .find(|record| record.synced_lyrics.is_some())
+            .or_else(|| records.iter().find(with_lyrics));
+
+        Ok(record.map(|record| Lyrics {
+            synced: record.synced_lyrics.clone(),
+            plain: record.plain_lyrics.clone(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_http::respond_once;
+
+    fn query() -> LyricsQuery {
+        LyricsQuery {
+            artist: Some("Daft Punk".to_string()),
+            title: "Get Lucky".to_string(),
+            album: None,
+        }
+    }
+
+    #[test]
+    fn synced_lyrics_are_preferred() {
+        let (url, requests) = respond_once(
+            200,
+            r#"[
+                {"id": 1, "trackName": "Get Lucky", "instrumental": false, "plainLyrics": "Plain only", "syncedLyrics": null},
+                {"id": 2, "trackName": "Get Lucky", "instrumental": false, "plainLyrics": "Like", "syncedLyrics": "[00:01.00]Like"}
+            ]"#,
+        );
+        let provider = LrclibProvider::new(&url, &NetworkConfig::default()).unwrap();
+
+        let lyrics = provider.fetch(&query()).unwrap().unwrap();
+        assert_eq!(lyrics.synced.as_deref(), Some("[00:01.00]Like"));
+
+        let request = reqwest::Url::parse(&format!("{}{}", url, requests.recv().unwrap())).unwrap();
+        assert_eq!(request.path(), "/api/search");
+        let params: Vec<(String, String)> = request.query_pairs().into_owned().collect();
+        assert!(params.contains(&("track_name".to_string(), "Get Lucky".to_string())));
+        assert!(params.contains(&("artist_name".to_string(), "Daft Punk".to_string())));
+    }
+
+    #[test]
+    fn instrumentals_and_empty_results_have_no_lyrics() {
+        let (url, _requests) = respond_once(
+            200,
+            r#"[{"id": 1, "instrumental": true, "plainLyrics": null, "syncedLyrics": null}]"#,
+        );
+        let provider = LrclibProvider::new(&url, &NetworkConfig::default()).unwrap();
+        assert_eq!(provider.fetch(&query()).unwrap(), None);
+
+        let (url, _requests) = respond_once(200, "[]");
+        let provider = LrclibProvider::new(&url, &NetworkConfig::default()).unwrap();
+        assert_eq!(provider.fetch(&query()).unwrap(), None);
+    }
+}
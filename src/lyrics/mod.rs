@@ -0,0 +1,109 @@
+pub mod local;
+pub mod lrclib;
+
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::app_config::NetworkConfig;
+
+// Track the lyrics are looked up for, from the tags of the downloaded file
+#[derive(Debug, Clone, PartialEq)]
+pub struct LyricsQuery {
+    pub artist: Option<String>,
+    pub title: String,
+    pub album: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Lyrics {
+    // LRC text with a "[mm:ss.xx]" timestamp on every line
+    pub synced: Option<String>,
+    pub plain: Option<String>,
+}
+
+impl Lyrics {
+    // Unsynced text, taken from the synced lyrics when there is no plain one
+    pub fn plain_text(&self) -> Option<String> {
+        self.plain
+            .clone()
+            .or_else(|| self.synced.as_deref().map(strip_timestamps))
+    }
+}
+
+// Source of lyrics for downloaded tracks
+pub trait LyricsProvider {
+    // Lyrics of the track, None when the provider doesn't have them
+    fn fetch(&self, query: &LyricsQuery) -> Result<Option<Lyrics>, Box<dyn std::error::Error>>;
+}
+
+// `lyrics` of a download profile, selects the provider by its `provider` field
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "provider", rename_all = "lowercase")]
+pub enum LyricsConfig {
+    // Files named "Artist - Title.lrc" or "Artist - Title.txt" in a folder
+    Local {
+        folder: PathBuf,
+    },
+    // LRCLIB web service, or a server with the same API
+    Lrclib {
+        #[serde(default = "lrclib::default_url")]
+        url: String,
+    },
+}
+
+impl LyricsConfig {
+    pub fn provider(
+        &self,
+        network: &NetworkConfig,
+    ) -> Result<Box<dyn LyricsProvider>, Box<dyn std::error::Error>> {
+        Ok(match self {
+            LyricsConfig::Local { folder } => Box::new(local::LocalProvider::new(folder)),
+            LyricsConfig::Lrclib { url } => Box::new(lrclib::LrclibProvider::new(url, network)?),
+        })
+    }
+}
+
+// Drop the "[mm:ss.xx]" timestamps and the "[ar:Artist]" style header tags of
+// LRC text
+pub fn strip_timestamps(lrc: &str) -> String {
+    let lines: Vec<&str> = lrc
+        .lines()
+        .filter_map(|line| {
+            let mut line = line.trim();
+            let mut tagged = false;
+            while let Some(rest) = line.strip_prefix('[') {
+                let (tag, text) = rest.split_once(']')?;
+                // Header tags are the whole line
+                if !tag.starts_with(|c: char| c.is_ascii_digit()) {
+                    return None;
+                }
+                line = text.trim_start();
+                tagged = true;
+            }
+            Some(line).filter(|line| tagged || !line.is_empty())
+        })
+        .collect();
+    lines.join("\n").trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timestamps_are_stripped_from_synced_lyrics() {
+        let lrc = "[ar:Daft Punk]\n[ti:Get Lucky]\n[00:01.00]Like the legend of the phoenix\n\
+                   [00:05.20][01:05.20]All ends with beginnings\n[00:09.00]\n[00:10.00]What keeps the planet spinning";
+        assert_eq!(
+            strip_timestamps(lrc),
+            "Like the legend of the phoenix\nAll ends with beginnings\n\nWhat keeps the planet spinning"
+        );
+
+        let lyrics = Lyrics {
+            synced: Some(lrc.to_string()),
+            plain: None,
+        };
+        assert!(lyrics.plain_text().unwrap().starts_with("Like the legend"));
+    }
+}
@@ -3,46 +3,112 @@ use std::sync::Mutex;
 use std::sync::{atomic::AtomicBool, Arc};
 
 mod app_config;
+mod cli;
+mod daemon;
+mod doctor;
 mod downloader;
 mod installer;
+mod lyrics;
+mod metadata;
 mod models;
+mod postprocess;
 mod runtime;
+mod shutdown;
+#[cfg(test)]
+mod test_http;
+mod tui;
 mod utils;
 mod views;
 
-use app_config::Config;
-use installer::{check_ffmpeg, check_yt_dlp, install_ffmpeg, install_yt_dlp};
-use models::translation::Translations;
+use app_config::{Config, ConfigCache};
+use clap::Parser;
+use cli::{Cli, CliCommand};
+use installer::{
+    plan::{install_missing, print_yt_dlp_download, Consent},
+    yt_dlp,
+};
+use models::{
+    history::History,
+    limits::{LimitOverrides, Limits},
+    translation::Translations,
+};
 
 use crate::runtime::{Runtime, RuntimeTrait};
 use crate::views::main::{MainMenuOption, MainView};
 use crate::views::View;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    println!("YouTube Downloader v0.2.0");
+    let cli = Cli::parse();
 
-    // Check if ffmpeg is installed
-    if !check_ffmpeg() {
-        println!("ffmpeg not found. Installing...");
-        install_ffmpeg()?;
+    // Validate the language files and exit, used by translators and CI
+    if cli.check_translations {
+        let issues = Translations::validate()?;
+        for issue in &issues {
+            println!("{}", issue);
+        }
+        if !issues.is_empty() {
+            println!("{} translation issue(s) found", issues.len());
+            std::process::exit(1);
+        }
+        println!("All translations are complete");
+        return Ok(());
     }
 
-    // Check if yt-dlp is installed
-    if !check_yt_dlp() {
-        println!("yt-dlp not found. Installing...");
-        install_yt_dlp()?;
+    // The client only talks to the daemon, it needs no dependencies
+    if let Some(CliCommand::Client { command }) = cli.command {
+        Translations::init(&Config::load());
+        return daemon::client::run(command);
     }
 
-    let mut runtime = Runtime {
-        url_buffer: Arc::new(Mutex::new(VecDeque::new())),
-        state: Arc::new(AtomicBool::new(true)),
-    };
+    // Diagnose before anything is installed, so the report shows what is missing
+    if let Some(CliCommand::Doctor) = cli.command {
+        let config = Config::load();
+        Translations::init(&config);
+        println!("{}", doctor::Report::collect(&config));
+        return Ok(());
+    }
+
+    println!("YouTube Downloader v0.2.0");
 
     // Load configuration
     let config = Config::load();
 
     // Initialize translations
     Translations::init(&config);
+    config.validate()?;
+
+    // Updates of the managed yt-dlp were agreed to when it was installed. The
+    // one on the PATH is used when they fail.
+    if yt_dlp::managed_path(&config.yt_dlp).is_some() {
+        if let Err(e) = yt_dlp::update(&config.yt_dlp, &config.network, print_yt_dlp_download) {
+            let error = config.network.redact(&e.to_string());
+            println!(
+                "{}",
+                Translations::t("yt_dlp_update_failed", Some(&[("error", error.into())]))
+            );
+        }
+    }
+
+    let consent = cli.install_consent();
+    install_missing(&config, consent)?;
+    if consent == Consent::DryRun {
+        return Ok(());
+    }
+
+    let mut runtime = Runtime {
+        url_buffer: Arc::new(Mutex::new(VecDeque::new())),
+        downloads: Arc::new(Mutex::new(Vec::new())),
+        history: Arc::new(Mutex::new(History::load())),
+        state: Arc::new(AtomicBool::new(true)),
+        limit_overrides: LimitOverrides {
+            limits: Limits {
+                max_rate: cli.limit_rate,
+                window: cli.window,
+            },
+            ignore_config: cli.no_limits,
+        },
+        config: ConfigCache::default(),
+    };
 
     println!(
         "{} {}",
@@ -58,6 +124,27 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         config.get_download_dir()
     );
 
+    shutdown::restore_queue(&runtime);
+
+    // Only the numbered menu blocks on input, the other modes notice the
+    // runtime stopping by themselves
+    let menu_mode = cli.command.is_none() && !cli.tui;
+    shutdown::install_handler(runtime.clone(), menu_mode)?;
+
+    if let Some(CliCommand::Daemon { http }) = cli.command {
+        let result = daemon::run(runtime.clone(), http);
+        shutdown::save_queue(&runtime);
+        return result;
+    }
+
+    if cli.tui {
+        let result = tui::run(runtime.clone());
+        shutdown::save_queue(&runtime);
+        result?;
+        println!("{}", Translations::t("app_stopped", None));
+        return Ok(());
+    }
+
     // Main program loop
     while runtime.start() {
         // MAIN MENU
@@ -67,6 +154,8 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         MainMenuOption::create_menu(&main_choice, &mut runtime);
     }
 
+    shutdown::save_queue(&runtime);
+
     println!("{}", Translations::t("app_stopped", None));
     Ok(())
 }
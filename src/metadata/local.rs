@@ -0,0 +1,219 @@
+use std::{fs, path::Path};
+
+use serde::Deserialize;
+
+use super::{video_id, MetadataProvider, TrackInfo};
+use crate::models::music::Music;
+
+// One entry of a mapping file. `key` is a video URL, a video ID or a video
+// title, the rest are the track details.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+struct Mapping {
+    key: String,
+    #[serde(flatten)]
+    info: TrackInfo,
+}
+
+// Track details from a local file, either a JSON array of objects or a CSV
+// file with a header row, both with the fields
+// key, artist, title, album, track, year and genre
+pub struct LocalProvider {
+    mappings: Vec<Mapping>,
+}
+
+impl LocalProvider {
+    pub fn load(file: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let text = fs::read_to_string(file)
+            .map_err(|e| format!("Can't read metadata file {}: {}", file.display(), e))?;
+        let is_csv = file
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+
+        let mappings = match is_csv {
+            true => parse_csv_mappings(&text),
+            false => serde_json::from_str(&text).map_err(|e| e.to_string()),
+        }
+        .map_err(|e| format!("Invalid metadata file {}: {}", file.display(), e))?;
+        Ok(Self { mappings })
+    }
+}
+
+impl MetadataProvider for LocalProvider {
+    fn lookup(&self, video: &Music) -> Result<Option<TrackInfo>, Box<dyn std::error::Error>> {
+        let id = video_id(&video.url);
+        let title = video.title.as_deref().map(str::trim);
+
+        // An exact URL or ID beats a title shared by several uploads
+        let found = self
+            .mappings
+            .iter()
+            .find(|mapping| mapping.key == video.url || Some(mapping.key.as_str()) == id)
+            .or_else(|| {
+                self.mappings.iter().find(|mapping| {
+                    title.is_some_and(|title| mapping.key.trim().eq_ignore_ascii_case(title))
+                })
+            });
+        Ok(found.map(|mapping| mapping.info.clone()))
+    }
+}
+
+fn parse_csv_mappings(text: &str) -> Result<Vec<Mapping>, String> {
+    let mut rows = parse_csv(text).into_iter();
+    let header = rows.next().ok_or("the file is empty")?;
+
+    let mut mappings = Vec::new();
+    for (line, row) in rows.enumerate() {
+        let mut mapping = Mapping {
+            key: String::new(),
+            info: TrackInfo::default(),
+        };
+        for (column, value) in header.iter().zip(row) {
+            let value = value.trim().to_string();
+            if value.is_empty() {
+                continue;
+            }
+            let number = |value: &str| {
+                value
+                    .parse()
+                    .map_err(|_| format!("row {}: {} isn't a number", line + 2, column))
+            };
+            match column.trim() {
+                "key" => mapping.key = value,
+                "artist" => mapping.info.artist = Some(value),
+                "title" => mapping.info.title = Some(value),
+                "album" => mapping.info.album = Some(value),
+                "track" => mapping.info.track = Some(number(&value)?),
+                "year" => mapping.info.year = Some(number(&value)? as i32),
+                "genre" => mapping.info.genre = Some(value),
+                other => return Err(format!("unknown column \"{}\"", other)),
+            }
+        }
+        if mapping.key.is_empty() {
+            return Err(format!("row {} has no key", line + 2));
+        }
+        mappings.push(mapping);
+    }
+    Ok(mappings)
+}
+
+// Rows of a CSV file. Fields may be quoted, with "" for a quote inside them.
+fn parse_csv(text: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (c, quoted) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            ('"', _) => quoted = !quoted,
+            (',', false) => row.push(std::mem::take(&mut field)),
+            ('\r', false) => {}
+            ('\n', false) => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            }
+            (c, _) => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+
+    // Blank lines
+    rows.retain(|row| row.iter().any(|field| !field.trim().is_empty()));
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn video(url: &str, title: &str) -> Music {
+        Music {
+            url: url.to_string(),
+            title: Some(title.to_string()),
+            downloaded_at: None,
+            playlist: None,
+            playlist_index: None,
+            loudness: None,
+            metadata: None,
+            subtitles: Vec::new(),
+        }
+    }
+
+    fn provider(name: &str, contents: &str) -> LocalProvider {
+        let file = std::env::temp_dir().join(format!(
+            "youtube_downloader_metadata_{}_{}",
+            std::process::id(),
+            name
+        ));
+        fs::write(&file, contents).unwrap();
+        let provider = LocalProvider::load(&file).unwrap();
+        fs::remove_file(file).unwrap();
+        provider
+    }
+
+    #[test]
+    fn json_mappings_match_by_url_id_or_title() {
+        let provider = provider(
+            "mapping.json",
+            r#"[
+                {"key": "dQw4w9WgXcQ", "artist": "Rick Astley", "title": "Never Gonna Give You Up", "year": 1987},
+                {"key": "Some Upload (Official Video)", "artist": "Someone", "album": "Album", "track": 3}
+            ]"#,
+        );
+
+        let found = provider
+            .lookup(&video("https://youtu.be/dQw4w9WgXcQ", "whatever"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(found.artist.as_deref(), Some("Rick Astley"));
+        assert_eq!(found.year, Some(1987));
+
+        let found = provider
+            .lookup(&video(
+                "https://youtu.be/other",
+                "some upload (official video)",
+            ))
+            .unwrap()
+            .unwrap();
+        assert_eq!(found.album.as_deref(), Some("Album"));
+        assert_eq!(found.track, Some(3));
+
+        assert_eq!(
+            provider
+                .lookup(&video("https://youtu.be/other", "Unknown"))
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn csv_mappings_allow_quoted_fields() {
+        let provider = provider(
+            "mapping.csv",
+            "key,artist,title,album,track\r\n\
+             https://youtu.be/abc,\"Simon, Garfunkel\",\"The \"\"Boxer\"\"\",,5\r\n\
+             \r\n",
+        );
+
+        let found = provider
+            .lookup(&video("https://youtu.be/abc", "x"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(found.artist.as_deref(), Some("Simon, Garfunkel"));
+        assert_eq!(found.title.as_deref(), Some("The \"Boxer\""));
+        assert_eq!(found.album, None);
+        assert_eq!(found.track, Some(5));
+
+        assert!(parse_csv_mappings("key,artist\nabc,x,extra\n").is_ok());
+        assert!(parse_csv_mappings("key,label\nabc,x\n").is_err());
+        assert!(parse_csv_mappings("key,track\nabc,three\n").is_err());
+    }
+}
@@ -0,0 +1,135 @@
+pub mod local;
+pub mod musicbrainz;
+
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::app_config::NetworkConfig;
+use crate::models::music::Music;
+
+// Canonical details of a track, unset ones come from the video
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TrackInfo {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub artist: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub album: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub track: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub year: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub genre: Option<String>,
+}
+
+// Source of track details for downloaded videos
+pub trait MetadataProvider {
+    // Details of the video, None when the provider doesn't know it
+    fn lookup(&self, video: &Music) -> Result<Option<TrackInfo>, Box<dyn std::error::Error>>;
+}
+
+// `metadata` in config.toml, selects the provider by its `provider` field
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "provider", rename_all = "lowercase")]
+pub enum MetadataConfig {
+    // JSON or CSV file mapping videos to track details, see `local`
+    Local {
+        file: PathBuf,
+    },
+    // MusicBrainz web service, or a server with the same API
+    MusicBrainz {
+        #[serde(default = "musicbrainz::default_url")]
+        url: String,
+        // Lowest search score (0-100) a recording is accepted with
+        #[serde(default = "musicbrainz::default_min_score")]
+        min_score: u8,
+    },
+}
+
+impl MetadataConfig {
+    // `title_patterns` split video titles into artist and title for searches
+    pub fn provider(
+        &self,
+        title_patterns: &[String],
+        network: &NetworkConfig,
+    ) -> Result<Box<dyn MetadataProvider>, Box<dyn std::error::Error>> {
+        Ok(match self {
+            MetadataConfig::Local { file } => Box::new(local::LocalProvider::load(file)?),
+            MetadataConfig::MusicBrainz { url, min_score } => {
+                Box::new(musicbrainz::MusicBrainzProvider::new(
+                    url,
+                    *min_score,
+                    title_patterns.to_vec(),
+                    network,
+                )?)
+            }
+        })
+    }
+}
+
+// Look the video up with the configured provider and store what was found on
+// it. A failed lookup doesn't stop the download, it is added to `warnings` and
+// the video keeps what it has; only a broken provider configuration is an
+// error.
+pub fn enrich(
+    video: &Music,
+    config: Option<&MetadataConfig>,
+    title_patterns: &[String],
+    network: &NetworkConfig,
+    warnings: &mut Vec<String>,
+) -> Result<Music, Box<dyn std::error::Error>> {
+    let mut video = video.clone();
+    if let Some(config) = config {
+        match config.provider(title_patterns, network)?.lookup(&video) {
+            Ok(metadata) => video.metadata = metadata,
+            Err(e) => warnings.push(network.redact(&format!("Metadata lookup failed: {}", e))),
+        }
+    }
+    Ok(video)
+}
+
+// ID of a YouTube video from its URL, e.g. "dQw4w9WgXcQ" from
+// "https://www.youtube.com/watch?v=dQw4w9WgXcQ" or "https://youtu.be/dQw4w9WgXcQ"
+pub fn video_id(url: &str) -> Option<&str> {
+    let (_, rest) = url.split_once("://").unwrap_or(("", url));
+    let (path, query) = rest.split_once('?').unwrap_or((rest, ""));
+
+    let from_query = query.split('&').find_map(|param| param.strip_prefix("v="));
+    let from_path = || {
+        let (host, path) = path.split_once('/')?;
+        let id = match path.split_once('/') {
+            Some(("shorts" | "embed" | "live", id)) => id,
+            _ if host.ends_with("youtu.be") => path,
+            _ => return None,
+        };
+        Some(id.trim_end_matches('/'))
+    };
+
+    from_query.or_else(from_path).filter(|id| !id.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn video_ids_are_taken_from_urls() {
+        assert_eq!(
+            video_id("https://www.youtube.com/watch?v=dQw4w9WgXcQ&list=PL1"),
+            Some("dQw4w9WgXcQ")
+        );
+        assert_eq!(
+            video_id("https://youtu.be/dQw4w9WgXcQ"),
+            Some("dQw4w9WgXcQ")
+        );
+        assert_eq!(
+            video_id("https://www.youtube.com/shorts/dQw4w9WgXcQ"),
+            Some("dQw4w9WgXcQ")
+        );
+        assert_eq!(video_id("https://www.youtube.com/playlist?list=PL1"), None);
+    }
+}
@@ -0,0 +1,289 @@
+use std::{
+    sync::Mutex,
+    thread,
+    time::{Duration, Instant},
+};
+
+use serde::Deserialize;
+
+use super::{MetadataProvider, TrackInfo};
+use crate::app_config::NetworkConfig;
+use crate::models::music::Music;
+use crate::postprocess::tags::parse_title;
+
+pub fn default_url() -> String {
+    "https://musicbrainz.org".to_string()
+}
+
+pub fn default_min_score() -> u8 {
+    90
+}
+
+// MusicBrainz allows a single request per second from an application
+const REQUEST_INTERVAL: Duration = Duration::from_secs(1);
+
+// Shared by every download running in parallel
+static LAST_REQUEST: Mutex<Option<Instant>> = Mutex::new(None);
+
+const TIMEOUT: Duration = Duration::from_secs(15);
+
+// Searches recordings through the MusicBrainz web service API
+pub struct MusicBrainzProvider {
+    url: String,
+    min_score: u8,
+    title_patterns: Vec<String>,
+    client: reqwest::blocking::Client,
+}
+
+#[derive(Debug, Deserialize)]
+struct SearchResult {
+    #[serde(default)]
+    recordings: Vec<Recording>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Recording {
+    #[serde(default)]
+    score: u8,
+    title: String,
+    #[serde(rename = "artist-credit", default)]
+    artist_credit: Vec<ArtistCredit>,
+    #[serde(rename = "first-release-date")]
+    first_release_date: Option<String>,
+    #[serde(default)]
+    releases: Vec<Release>,
+    #[serde(default)]
+    tags: Vec<Tag>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ArtistCredit {
+    name: String,
+    #[serde(default)]
+    joinphrase: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Release {
+    title: String,
+    #[serde(default)]
+    media: Vec<Medium>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Medium {
+    #[serde(rename = "track-offset")]
+    track_offset: Option<u32>,
+    #[serde(default)]
+    track: Vec<Track>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Track {
+    number: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Tag {
+    name: String,
+    #[serde(default)]
+    count: i32,
+}
+
+impl Recording {
+    fn into_track_info(self) -> TrackInfo {
+        let artist: String = self
+            .artist_credit
+            .iter()
+            .map(|credit| format!("{}{}", credit.name, credit.joinphrase))
+            .collect();
+        let release = self.releases.into_iter().next();
+        let track = release
+            .as_ref()
+            .and_then(|release| release.media.first())
+            .and_then(|medium| {
+                medium
+                    .track
+                    .first()
+                    .and_then(|track| track.number.parse().ok())
+                    .or(medium.track_offset.map(|offset| offset + 1))
+            });
+
+        TrackInfo {
+            artist: Some(artist).filter(|artist| !artist.is_empty()),
+            title: Some(self.title),
+            album: release.map(|release| release.title),
+            track,
+            year: self
+                .first_release_date
+                .and_then(|date| date.get(..4)?.parse().ok()),
+            genre: self
+                .tags
+                .into_iter()
+                .max_by_key(|tag| tag.count)
+                .map(|tag| tag.name),
+        }
+    }
+}
+
+// Quote a value for a Lucene search query
+fn phrase(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+impl MusicBrainzProvider {
+    pub fn new(
+        url: &str,
+        min_score: u8,
+        title_patterns: Vec<String>,
+        network: &NetworkConfig,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self {
+            url: url.trim_end_matches('/').to_string(),
+            min_score,
+            title_patterns,
+            client: network.http_client(TIMEOUT)?,
+        })
+    }
+
+    fn query(&self, title: &str) -> String {
+        match parse_title(title, &self.title_patterns) {
+            Some((Some(artist), title)) => {
+                format!(
+                    "recording:{} AND artist:{}",
+                    phrase(&title),
+                    phrase(&artist)
+                )
+            }
+            Some((None, title)) => format!("recording:{}", phrase(&title)),
+            None => format!("recording:{}", phrase(title)),
+        }
+    }
+
+    fn wait_for_turn(&self) {
+        let mut last = LAST_REQUEST.lock().unwrap();
+        if let Some(elapsed) = last.map(|last| last.elapsed()) {
+            if elapsed < REQUEST_INTERVAL {
+                thread::sleep(REQUEST_INTERVAL - elapsed);
+            }
+        }
+        *last = Some(Instant::now());
+    }
+}
+
+impl MetadataProvider for MusicBrainzProvider {
+    fn lookup(&self, video: &Music) -> Result<Option<TrackInfo>, Box<dyn std::error::Error>> {
+        let Some(title) = &video.title else {
+            return Ok(None);
+        };
+
+        self.wait_for_turn();
+        let result: SearchResult = self
+            .client
+            .get(format!("{}/ws/2/recording", self.url))
+            .query(&[
+                ("query", self.query(title).as_str()),
+                ("fmt", "json"),
+                ("limit", "1"),
+            ])
+            .send()?
+            .error_for_status()?
+            .json()?;
+
+        Ok(result
+            .recordings
+            .into_iter()
+            .next()
+            .filter(|recording| recording.score >= self.min_score)
+            .map(Recording::into_track_info))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_http::respond_once;
+
+    const RESPONSE: &str = r#"{
+        "created": "2024-01-01T00:00:00.000Z",
+        "count": 1,
+        "offset": 0,
+        "recordings": [{
+            "id": "8f3471b5-7e6a-48da-86a9-c1c07a0f47ae",
+            "score": 100,
+            "title": "Get Lucky",
+            "artist-credit": [
+                {"name": "Daft Punk", "joinphrase": " feat. "},
+                {"name": "Pharrell Williams"}
+            ],
+            "first-release-date": "2013-04-19",
+            "releases": [{
+                "title": "Random Access Memories",
+                "media": [{"position": 1, "track": [{"number": "8", "title": "Get Lucky"}], "track-offset": 7}]
+            }],
+            "tags": [{"count": 1, "name": "disco"}, {"count": 4, "name": "electronic"}]
+        }]
+    }"#;
+
+    fn video(title: &str) -> Music {
+        Music {
+            url: "https://youtu.be/abc".to_string(),
+            title: Some(title.to_string()),
+            downloaded_at: None,
+            playlist: None,
+            playlist_index: None,
+            loudness: None,
+            metadata: None,
+            subtitles: Vec::new(),
+        }
+    }
+
+    fn provider(url: &str, min_score: u8) -> MusicBrainzProvider {
+        let patterns = vec!["{artist} - {title}".to_string()];
+        MusicBrainzProvider::new(url, min_score, patterns, &NetworkConfig::default()).unwrap()
+    }
+
+    #[test]
+    fn recordings_are_searched_by_artist_and_title() {
+        let (url, requests) = respond_once(200, RESPONSE);
+
+        let info = provider(&url, 90)
+            .lookup(&video("Daft Punk - Get Lucky (Official Audio)"))
+            .unwrap()
+            .unwrap();
+
+        let request = reqwest::Url::parse(&format!("{}{}", url, requests.recv().unwrap())).unwrap();
+        assert_eq!(request.path(), "/ws/2/recording");
+        let query: Vec<(String, String)> = request.query_pairs().into_owned().collect();
+        assert!(query.contains(&("fmt".to_string(), "json".to_string())));
+        assert!(query.contains(&(
+            "query".to_string(),
+            r#"recording:"Get Lucky (Official Audio)" AND artist:"Daft Punk""#.to_string()
+        )));
+
+        assert_eq!(
+            info,
+            TrackInfo {
+                artist: Some("Daft Punk feat. Pharrell Williams".to_string()),
+                title: Some("Get Lucky".to_string()),
+                album: Some("Random Access Memories".to_string()),
+                track: Some(8),
+                year: Some(2013),
+                genre: Some("electronic".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn weak_matches_are_ignored() {
+        let (url, _requests) = respond_once(200, RESPONSE);
+        let info = provider(&url, 101).lookup(&video("Get Lucky")).unwrap();
+        assert_eq!(info, None);
+    }
+
+    #[test]
+    fn server_errors_are_reported() {
+        let (url, _requests) = respond_once(503, "busy");
+        assert!(provider(&url, 90).lookup(&video("Get Lucky")).is_err());
+    }
+}
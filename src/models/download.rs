@@ -0,0 +1,128 @@
+use std::{
+    sync::{
+        atomic::{AtomicU8, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::{music::Music, translation::Translations};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum DownloadState {
+    Waiting,
+    // Held until the download window opens
+    Scheduled,
+    // Percentage between 0 and 100
+    Downloading(f32),
+    // Seconds it took to download
+    Finished(u64),
+    Failed(String),
+    // Stopped with the partial file kept, it continues on the next download
+    Paused,
+    // Stopped and the partial file removed
+    Cancelled,
+}
+
+// Why a download was stopped before it finished
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Interruption {
+    Paused,
+    Cancelled,
+}
+
+const RUNNING: u8 = 0;
+const PAUSED: u8 = 1;
+const CANCELLED: u8 = 2;
+
+// Shared flag a running download checks to know whether it should stop
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicU8>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn interrupt(&self, interruption: Interruption) {
+        match interruption {
+            // Pausing never turns an earlier cancel back into a pause
+            Interruption::Paused => {
+                let _ =
+                    self.0
+                        .compare_exchange(RUNNING, PAUSED, Ordering::SeqCst, Ordering::SeqCst);
+            }
+            Interruption::Cancelled => self.0.store(CANCELLED, Ordering::SeqCst),
+        }
+    }
+
+    pub fn interruption(&self) -> Option<Interruption> {
+        match self.0.load(Ordering::SeqCst) {
+            PAUSED => Some(Interruption::Paused),
+            CANCELLED => Some(Interruption::Cancelled),
+            _ => None,
+        }
+    }
+}
+
+// An item of the current download batch and how far along it is
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Download {
+    pub music: Music,
+    pub state: DownloadState,
+    // Steps that failed without failing the download, e.g. a metadata lookup
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+    #[serde(skip)]
+    pub token: CancellationToken,
+}
+
+impl Download {
+    pub fn new(music: Music) -> Self {
+        Self {
+            music,
+            state: DownloadState::Waiting,
+            warnings: Vec::new(),
+            token: CancellationToken::new(),
+        }
+    }
+
+    // Waiting or downloading, i.e. it can still be paused or cancelled
+    pub fn is_active(&self) -> bool {
+        matches!(
+            self.state,
+            DownloadState::Waiting | DownloadState::Scheduled | DownloadState::Downloading(_)
+        )
+    }
+}
+
+impl DownloadState {
+    // Short translated description, e.g. "waiting" or "42.0%"
+    pub fn describe(&self) -> String {
+        match self {
+            DownloadState::Waiting => Translations::t("download_state_waiting", None),
+            DownloadState::Scheduled => Translations::t("download_state_scheduled", None),
+            DownloadState::Downloading(percent) => format!("{:.1}%", percent),
+            DownloadState::Finished(seconds) => Translations::t(
+                "download_state_finished",
+                Some(&[("duration", Duration::from_secs(*seconds).into())]),
+            ),
+            DownloadState::Failed(error) => {
+                Translations::t("download_state_failed", Some(&[("error", error.into())]))
+            }
+            DownloadState::Paused => Translations::t("download_state_paused", None),
+            DownloadState::Cancelled => Translations::t("download_state_cancelled", None),
+        }
+    }
+}
+
+impl From<Interruption> for DownloadState {
+    fn from(interruption: Interruption) -> Self {
+        match interruption {
+            Interruption::Paused => DownloadState::Paused,
+            Interruption::Cancelled => DownloadState::Cancelled,
+        }
+    }
+}
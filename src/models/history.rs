@@ -1,55 +1,193 @@
-use std::{fs, path::Path};
+use std::{
+    collections::HashSet,
+    fs::{self, File},
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+};
 
 use serde::{Deserialize, Serialize};
 
 use crate::models::music::Music;
 
-#[derive(Debug, Deserialize, Serialize)]
+const HISTORY_PATH: &str = "download_history.json";
+
+// Numbers the temporary files of saves from this process
+static SAVE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct History {
     pub downloads: Vec<Music>,
+    // Recorded since the file was last written, see `flush`
+    #[serde(skip)]
+    pending: Vec<Music>,
 }
 
 impl History {
     pub fn load() -> Self {
-        let history_path = "download_history.json";
-        if Path::new(history_path).exists() {
+        Self::load_from(Path::new(HISTORY_PATH))
+    }
+
+    fn load_from(history_path: &Path) -> Self {
+        if history_path.exists() {
             match fs::read_to_string(history_path) {
                 Ok(content) => match serde_json::from_str::<History>(&content) {
                     Ok(history) => return history,
                     Err(e) => {
                         println!("Error parsing history file: {}", e);
-                        return Self {
-                            downloads: Vec::new(),
-                        };
+                        return Self::default();
                     }
                 },
-                Err(_) => {
-                    return Self {
-                        downloads: Vec::new(),
-                    }
-                }
+                Err(_) => return Self::default(),
             }
         }
-        Self {
-            downloads: Vec::new(),
-        }
+        Self::default()
+    }
+
+    // The file again, e.g. with entries saved by the daemon, plus what this
+    // instance recorded but hasn't written yet
+    pub fn reload(&mut self) {
+        let mut reloaded = Self::load();
+        reloaded.merge(&self.pending);
+        reloaded.pending = std::mem::take(&mut self.pending);
+        *self = reloaded;
     }
 
-    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
-        let history_path = "download_history.json";
+    // Written to a temporary file first and renamed over the old one, so a crash
+    // while saving never leaves a truncated history behind
+    fn save_to(&self, history_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let temp_path = sibling(
+            history_path,
+            &format!(
+                "{}.{}.tmp",
+                process::id(),
+                SAVE_COUNTER.fetch_add(1, Ordering::SeqCst)
+            ),
+        );
         let json = serde_json::to_string_pretty(self)?;
-        fs::write(history_path, json)?;
+        fs::write(&temp_path, json)?;
+        if let Err(e) = fs::rename(&temp_path, history_path) {
+            let _ = fs::remove_file(&temp_path);
+            return Err(e.into());
+        }
         Ok(())
     }
 
+    // Add a finished download. It is written to the file by the next `flush`,
+    // once for a whole batch.
     pub fn add(&mut self, video: &Music) {
         let mut video_copy = video.clone();
         let now = chrono::Local::now().to_string();
         video_copy.downloaded_at = Some(now);
+        self.downloads.push(video_copy.clone());
+        self.pending.push(video_copy);
+    }
 
-        self.downloads.push(video_copy);
-        if let Err(e) = self.save() {
+    // Write the downloads added since the last flush
+    pub fn flush(&mut self) {
+        if let Err(e) = self.flush_to(Path::new(HISTORY_PATH)) {
             println!("Failed to save download history: {}", e);
         }
     }
+
+    // Another instance (the daemon, or a second terminal) may have added
+    // entries since this one loaded the file, so the file is read again and
+    // merged while holding a lock on it
+    fn flush_to(&mut self, history_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+
+        let lock = File::create(sibling(history_path, "lock"))?;
+        lock.lock()?;
+        let mut merged = Self::load_from(history_path);
+        merged.merge(&self.pending);
+        merged.save_to(history_path)?;
+        *self = merged;
+        Ok(())
+    }
+
+    // Append the entries that aren't in the history yet
+    fn merge(&mut self, pending: &[Music]) {
+        let saved: HashSet<_> = self.downloads.iter().map(entry_key).collect();
+        self.downloads.extend(
+            pending
+                .iter()
+                .filter(|music| !saved.contains(&entry_key(music)))
+                .cloned(),
+        );
+    }
+}
+
+// Entries are told apart by what was downloaded and when
+fn entry_key(music: &Music) -> (String, Option<String>) {
+    (music.url.clone(), music.downloaded_at.clone())
+}
+
+// "download_history.json.<extension>" next to the history file
+fn sibling(history_path: &Path, extension: &str) -> PathBuf {
+    let mut name = history_path.as_os_str().to_owned();
+    name.push(format!(".{}", extension));
+    PathBuf::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn music(url: &str) -> Music {
+        Music {
+            url: url.to_string(),
+            title: None,
+            downloaded_at: None,
+            playlist: None,
+            playlist_index: None,
+            loudness: None,
+            metadata: None,
+            subtitles: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn entries_saved_by_other_instances_are_kept() {
+        let dir =
+            std::env::temp_dir().join(format!("youtube_downloader_history_{}", process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("download_history.json");
+
+        // Both loaded the empty history before either downloaded anything
+        let mut first = History::load_from(&path);
+        let mut second = History::load_from(&path);
+        first.add(&music("first"));
+        first.flush_to(&path).unwrap();
+        second.add(&music("second"));
+        second.flush_to(&path).unwrap();
+        first.add(&music("third"));
+        first.add(&music("fourth"));
+        assert_eq!(History::load_from(&path).downloads.len(), 2);
+        first.flush_to(&path).unwrap();
+
+        let urls: Vec<String> = History::load_from(&path)
+            .downloads
+            .into_iter()
+            .map(|music| music.url)
+            .collect();
+        assert_eq!(urls, ["first", "second", "third", "fourth"]);
+        assert_eq!(first.downloads.len(), 4);
+        assert!(first.pending.is_empty());
+        let leftovers = fs::read_dir(&dir)
+            .unwrap()
+            .filter(|entry| {
+                entry
+                    .as_ref()
+                    .unwrap()
+                    .file_name()
+                    .to_string_lossy()
+                    .ends_with(".tmp")
+            })
+            .count();
+        assert_eq!(leftovers, 0);
+        fs::remove_dir_all(dir).unwrap();
+    }
 }
@@ -1,6 +1,8 @@
 use std::fmt;
 use std::str::FromStr;
+use std::time::Duration;
 
+use chrono::{DateTime, FixedOffset};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash, Copy)]
@@ -12,7 +14,7 @@ pub enum Language {
 
 impl Language {
     // Convert the language enum to a lowercase string that can be used for filenames
-    pub fn to_filename(&self) -> String {
+    pub fn to_filename(self) -> String {
         format!("{}.toml", self.to_string().to_lowercase())
     }
 
@@ -20,11 +22,155 @@ impl Language {
     pub fn all() -> Vec<Language> {
         vec![Language::English, Language::Hungarian]
     }
+
+    // ISO 639-1 code of the language, as used in locale names
+    pub fn code(self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::Hungarian => "hu",
+        }
+    }
+
+    // Map a locale name like "hu_HU.UTF-8", "en-US" or "hu" to a supported language
+    pub fn from_locale(locale: &str) -> Option<Language> {
+        let code = locale
+            .split(['_', '-', '.', '@'])
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+
+        Language::all().into_iter().find(|l| l.code() == code)
+    }
+
+    // Detect the UI language from the system locale, falling back to English.
+    // The POSIX variables are checked first in their usual precedence, then the
+    // OS-specific APIs (Windows/macOS user preferences).
+    pub fn detect() -> Language {
+        let posix_locale = ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|var| std::env::var(var).ok())
+            .find(|value| !is_neutral_locale(value));
+
+        match posix_locale {
+            Some(locale) => Language::from_locale(&locale),
+            None => sys_locale::get_locales().find_map(|locale| Language::from_locale(&locale)),
+        }
+        .unwrap_or(Language::English)
+    }
+}
+
+// "C", "POSIX" and their variants like "C.UTF-8" carry no language information
+fn is_neutral_locale(locale: &str) -> bool {
+    let code = locale
+        .split(['_', '-', '.', '@'])
+        .next()
+        .unwrap_or_default();
+    code.is_empty() || code == "C" || code == "POSIX"
+}
+
+// CLDR plural categories used to select the form of a pluralized translation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PluralCategory {
+    Zero,
+    One,
+    Two,
+    Few,
+    Many,
+    Other,
+}
+
+impl PluralCategory {
+    // Key of the form in the language files, e.g. `starting_download.one`
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PluralCategory::Zero => "zero",
+            PluralCategory::One => "one",
+            PluralCategory::Two => "two",
+            PluralCategory::Few => "few",
+            PluralCategory::Many => "many",
+            PluralCategory::Other => "other",
+        }
+    }
+}
+
+impl FromStr for PluralCategory {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "zero" => Ok(PluralCategory::Zero),
+            "one" => Ok(PluralCategory::One),
+            "two" => Ok(PluralCategory::Two),
+            "few" => Ok(PluralCategory::Few),
+            "many" => Ok(PluralCategory::Many),
+            "other" => Ok(PluralCategory::Other),
+            _ => Err(format!("Unknown plural category: {}", s)),
+        }
+    }
+}
+
+impl Language {
+    // Cardinal plural rules from CLDR for integer counts.
+    // Both English and Hungarian only distinguish "one" and "other".
+    pub fn plural_category(self, count: i64) -> PluralCategory {
+        match self {
+            Language::English | Language::Hungarian => match count {
+                1 => PluralCategory::One,
+                _ => PluralCategory::Other,
+            },
+        }
+    }
+
+    // Format an integer with the language's digit grouping
+    pub fn format_number(self, number: i64) -> String {
+        let separator = match self {
+            Language::English => ",",
+            Language::Hungarian => "\u{a0}",
+        };
+
+        let digits = number.unsigned_abs().to_string();
+        let mut result = String::new();
+        for (i, digit) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i).is_multiple_of(3) {
+                result.push_str(separator);
+            }
+            result.push(digit);
+        }
+
+        if number < 0 {
+            result.insert(0, '-');
+        }
+        result
+    }
+
+    // Format a timestamp the way dates are usually written in the language
+    pub fn format_date(self, date: &DateTime<FixedOffset>) -> String {
+        let format = match self {
+            Language::English => "%Y-%m-%d %H:%M",
+            Language::Hungarian => "%Y. %m. %d. %H:%M",
+        };
+        date.format(format).to_string()
+    }
+
+    // Format an elapsed time with the language's unit abbreviations,
+    // e.g. "42s" or "1m 05s"
+    pub fn format_duration(self, duration: Duration) -> String {
+        let (minute, second) = match self {
+            Language::English => ("m", "s"),
+            Language::Hungarian => ("p", "mp"),
+        };
+
+        let seconds = duration.as_secs();
+        match seconds / 60 {
+            0 => format!("{}{}", seconds, second),
+            minutes => format!("{}{} {:02}{}", minutes, minute, seconds % 60, second),
+        }
+    }
 }
 
 impl Default for Language {
     fn default() -> Self {
-        Language::Hungarian
+        Language::detect()
     }
 }
 
@@ -48,3 +194,63 @@ impl FromStr for Language {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locales_map_to_supported_languages() {
+        let cases = [
+            ("hu_HU.UTF-8", Some(Language::Hungarian)),
+            ("hu", Some(Language::Hungarian)),
+            ("HU-hu", Some(Language::Hungarian)),
+            ("en_US.UTF-8", Some(Language::English)),
+            ("en-GB", Some(Language::English)),
+            ("en_US@euro", Some(Language::English)),
+            ("de_DE.UTF-8", None),
+            ("C", None),
+            ("", None),
+        ];
+        for (locale, language) in cases {
+            assert_eq!(Language::from_locale(locale), language, "{}", locale);
+        }
+
+        for locale in ["C", "C.UTF-8", "C.utf8", "POSIX", ""] {
+            assert!(is_neutral_locale(locale), "{}", locale);
+        }
+        for locale in ["en_US.UTF-8", "hu", "ca_ES"] {
+            assert!(!is_neutral_locale(locale), "{}", locale);
+        }
+    }
+
+    #[test]
+    fn plural_categories_and_durations_follow_the_language() {
+        for language in Language::all() {
+            assert_eq!(
+                language.plural_category(1),
+                PluralCategory::One,
+                "{}",
+                language
+            );
+            for count in [0, 2, 5, 21, 100] {
+                assert_eq!(
+                    language.plural_category(count),
+                    PluralCategory::Other,
+                    "{} {}",
+                    language,
+                    count
+                );
+            }
+        }
+
+        assert_eq!(
+            Language::English.format_duration(Duration::from_secs(42)),
+            "42s"
+        );
+        assert_eq!(
+            Language::Hungarian.format_duration(Duration::from_secs(125)),
+            "2p 05mp"
+        );
+    }
+}
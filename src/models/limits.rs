@@ -0,0 +1,206 @@
+use std::{fmt, str::FromStr};
+
+use chrono::{Local, NaiveTime};
+use serde::{Deserialize, Serialize};
+
+// Download speed in bytes per second, written like yt-dlp's `--limit-rate`
+// ("500K", "2M"), with 1024-based suffixes
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Rate(pub u64);
+
+impl FromStr for Rate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (number, multiplier) = match s.chars().last().map(|c| c.to_ascii_uppercase()) {
+            Some('K') => (&s[..s.len() - 1], 1024.0),
+            Some('M') => (&s[..s.len() - 1], 1024.0 * 1024.0),
+            Some('G') => (&s[..s.len() - 1], 1024.0 * 1024.0 * 1024.0),
+            _ => (s, 1.0),
+        };
+
+        match number.trim().parse::<f64>() {
+            Ok(value) if value * multiplier >= 1.0 => Ok(Rate((value * multiplier) as u64)),
+            _ => Err(format!(
+                "Invalid download speed \"{}\", use e.g. 500K or 2M",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Rate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            bytes if bytes >= 1024 * 1024 && bytes.is_multiple_of(1024 * 1024) => {
+                write!(f, "{}M", bytes / (1024 * 1024))
+            }
+            bytes if bytes >= 1024 && bytes.is_multiple_of(1024) => write!(f, "{}K", bytes / 1024),
+            bytes => write!(f, "{}", bytes),
+        }
+    }
+}
+
+impl TryFrom<String> for Rate {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Rate> for String {
+    fn from(rate: Rate) -> Self {
+        rate.to_string()
+    }
+}
+
+// Time of day downloads may run in, e.g. "22:00-06:00". The end may be
+// earlier than the start, in which case the window spans midnight.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct DownloadWindow {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl DownloadWindow {
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start < self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.contains(Local::now().time())
+    }
+}
+
+impl FromStr for DownloadWindow {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || format!("Invalid download window \"{}\", use e.g. 22:00-06:00", s);
+        let (start, end) = s.split_once('-').ok_or_else(error)?;
+        let parse =
+            |time: &str| NaiveTime::parse_from_str(time.trim(), "%H:%M").map_err(|_| error());
+        let window = DownloadWindow {
+            start: parse(start)?,
+            end: parse(end)?,
+        };
+
+        match window.start == window.end {
+            true => Err(error()),
+            false => Ok(window),
+        }
+    }
+}
+
+impl fmt::Display for DownloadWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}-{}",
+            self.start.format("%H:%M"),
+            self.end.format("%H:%M")
+        )
+    }
+}
+
+impl TryFrom<String> for DownloadWindow {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<DownloadWindow> for String {
+    fn from(window: DownloadWindow) -> Self {
+        window.to_string()
+    }
+}
+
+// How fast and when downloads may run
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Limits {
+    // Shared by all downloads running at the same time
+    pub max_rate: Option<Rate>,
+    pub window: Option<DownloadWindow>,
+}
+
+impl Limits {
+    // Share of the global cap for each of `workers` parallel downloads
+    pub fn rate_per_worker(&self, workers: usize) -> Option<Rate> {
+        self.max_rate
+            .map(|Rate(total)| Rate((total / workers.max(1) as u64).max(1)))
+    }
+
+    pub fn with_overrides(self, overrides: &LimitOverrides) -> Limits {
+        let base = match overrides.ignore_config {
+            true => Limits::default(),
+            false => self,
+        };
+        Limits {
+            max_rate: overrides.limits.max_rate.or(base.max_rate),
+            window: overrides.limits.window.or(base.window),
+        }
+    }
+}
+
+// Limits given on the command line, they replace the configured ones for a
+// single run without being saved
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LimitOverrides {
+    pub limits: Limits,
+    // Ignore the configured limits that aren't overridden
+    pub ignore_config: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rates_parse_and_display_like_yt_dlp() {
+        assert_eq!("500K".parse(), Ok(Rate(500 * 1024)));
+        assert_eq!("1.5m".parse(), Ok(Rate(1536 * 1024)));
+        assert_eq!("2000".parse(), Ok(Rate(2000)));
+        assert!("fast".parse::<Rate>().is_err());
+        assert!("0".parse::<Rate>().is_err());
+        assert_eq!(Rate(2 * 1024 * 1024).to_string(), "2M");
+        assert_eq!(Rate(1536).to_string(), "1536");
+    }
+
+    #[test]
+    fn windows_may_span_midnight() {
+        let time = |t: &str| NaiveTime::parse_from_str(t, "%H:%M").unwrap();
+        let night: DownloadWindow = "22:00-06:00".parse().unwrap();
+        assert!(night.contains(time("23:30")));
+        assert!(night.contains(time("05:59")));
+        assert!(!night.contains(time("06:00")));
+        assert!(!night.contains(time("12:00")));
+
+        let day: DownloadWindow = "09:00-17:00".parse().unwrap();
+        assert!(day.contains(time("09:00")));
+        assert!(!day.contains(time("17:00")));
+
+        assert!("10:00-10:00".parse::<DownloadWindow>().is_err());
+        assert!("25:00-06:00".parse::<DownloadWindow>().is_err());
+    }
+
+    #[test]
+    fn rate_is_split_between_workers() {
+        let limits = Limits {
+            max_rate: Some(Rate(4 * 1024 * 1024)),
+            window: None,
+        };
+        assert_eq!(limits.rate_per_worker(4), Some(Rate(1024 * 1024)));
+        assert_eq!(Limits::default().rate_per_worker(4), None);
+    }
+}
@@ -1,4 +1,8 @@
+pub mod download;
 pub mod history;
 pub mod language;
+pub mod limits;
 pub mod music;
+pub mod profile;
+pub mod queue;
 pub mod translation;
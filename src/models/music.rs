@@ -1,8 +1,37 @@
+use std::path::PathBuf;
+
+use chrono::{DateTime, FixedOffset};
 use serde::{Deserialize, Serialize};
 
+use crate::metadata::TrackInfo;
+use crate::postprocess::loudness::Loudness;
+
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct Music {
     pub url: String,
     pub title: Option<String>,
     pub downloaded_at: Option<String>,
+    // Title of the playlist the video was added from and its place in it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub playlist: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub playlist_index: Option<u32>,
+    // Measured after downloading, when the profile handles loudness
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub loudness: Option<Loudness>,
+    // Track details found by the metadata provider
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<TrackInfo>,
+    // Subtitle files saved next to the download
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub subtitles: Vec<PathBuf>,
+}
+
+impl Music {
+    // Parse `downloaded_at`, which is stored in chrono's default `Display` format
+    pub fn downloaded_date(&self) -> Option<DateTime<FixedOffset>> {
+        self.downloaded_at
+            .as_deref()
+            .and_then(|date| DateTime::parse_from_str(date, "%Y-%m-%d %H:%M:%S%.f %:z").ok())
+    }
 }
@@ -0,0 +1,296 @@
+use serde::{Deserialize, Serialize};
+
+use crate::lyrics::LyricsConfig;
+use crate::postprocess::{artwork::ArtworkSettings, loudness::LoudnessSettings, tags::TagSettings};
+
+// Named set of options for how items are downloaded, selected with `profile`
+// in config.toml
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DownloadProfile {
+    // Audio format yt-dlp converts to, e.g. "mp3", "opus" or "m4a"
+    pub audio_format: String,
+    // SponsorBlock categories cut out of the audio, e.g. "sponsor", "intro"
+    // or "music_offtopic"
+    pub sponsorblock_remove: Vec<String>,
+    // SponsorBlock categories kept, but marked as chapters
+    pub sponsorblock_mark: Vec<String>,
+    // Store the video's chapters in the file
+    pub embed_chapters: bool,
+    // Turn every chapter into its own numbered track, e.g. for a full album
+    // upload. The tracks replace the full-length file.
+    pub split_chapters: bool,
+    // Name of the audio file without extension, e.g. "{artist} - {title}".
+    // {album}, {track} and {year} are filled in as well, from the metadata
+    // provider, the video title or the playlist. The video title when unset.
+    pub filename: Option<String>,
+    // Save videos added from a playlist in a folder named after the playlist
+    pub playlist_folders: bool,
+    // Passed to yt-dlp after the global extra arguments
+    pub extra_args: Vec<String>,
+    pub tags: TagSettings,
+    pub artwork: ArtworkSettings,
+    pub loudness: LoudnessSettings,
+    // Fetches lyrics for every track, off when unset
+    pub lyrics: Option<LyricsConfig>,
+    pub subtitles: SubtitleSettings,
+}
+
+impl Default for DownloadProfile {
+    fn default() -> Self {
+        Self {
+            audio_format: "mp3".to_string(),
+            sponsorblock_remove: Vec::new(),
+            sponsorblock_mark: Vec::new(),
+            embed_chapters: false,
+            split_chapters: false,
+            filename: None,
+            playlist_folders: false,
+            extra_args: Vec::new(),
+            tags: TagSettings::default(),
+            artwork: ArtworkSettings::default(),
+            loudness: LoudnessSettings::default(),
+            lyrics: None,
+            subtitles: SubtitleSettings::default(),
+        }
+    }
+}
+
+impl DownloadProfile {
+    // yt-dlp arguments for the options of the profile
+    pub fn args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if !self.sponsorblock_remove.is_empty() {
+            args.push("--sponsorblock-remove".to_string());
+            args.push(self.sponsorblock_remove.join(","));
+        }
+        if !self.sponsorblock_mark.is_empty() {
+            args.push("--sponsorblock-mark".to_string());
+            args.push(self.sponsorblock_mark.join(","));
+        }
+        if self.embed_chapters {
+            args.push("--embed-chapters".to_string());
+        }
+        if self.split_chapters {
+            args.push("--split-chapters".to_string());
+        }
+        args.extend(self.artwork.args());
+        args.extend(self.subtitles.args());
+        args
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SubtitleFormat {
+    Srt,
+    Vtt,
+}
+
+impl SubtitleFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            SubtitleFormat::Srt => "srt",
+            SubtitleFormat::Vtt => "vtt",
+        }
+    }
+}
+
+// Subtitles saved next to the downloaded file, and embedded in it when asked
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SubtitleSettings {
+    // Language codes like "en" or "hu", or yt-dlp patterns like "en.*". No
+    // subtitles are downloaded when empty.
+    pub languages: Vec<String>,
+    // Use YouTube's automatic captions for languages without subtitles
+    pub auto_generated: bool,
+    // Converted to this format, kept as YouTube sends them when unset
+    pub format: Option<SubtitleFormat>,
+    // Also store them in the audio file. yt-dlp can only do this for m4a
+    // files (the aac, alac and m4a audio formats), and skips it for others.
+    pub embed: bool,
+}
+
+impl SubtitleSettings {
+    pub fn args(&self) -> Vec<String> {
+        if self.languages.is_empty() {
+            return Vec::new();
+        }
+        let mut args = vec!["--write-subs".to_string()];
+        if self.auto_generated {
+            args.push("--write-auto-subs".to_string());
+        }
+        args.push("--sub-langs".to_string());
+        args.push(self.languages.join(","));
+        if let Some(format) = self.format {
+            args.push("--convert-subs".to_string());
+            args.push(format.extension().to_string());
+        }
+        // The files are kept as well, yt-dlp only removes them after embedding
+        // when --write-subs isn't given
+        if self.embed {
+            args.push("--embed-subs".to_string());
+        }
+        args
+    }
+}
+
+// yt-dlp options the application sets itself; passing them again would break
+// progress tracking, file naming or the other settings
+const CONTROLLED_OPTIONS: &[&str] = &[
+    "-o",
+    "--output",
+    "-P",
+    "--paths",
+    "-x",
+    "--extract-audio",
+    "--audio-format",
+    "--newline",
+    "--progress-template",
+    // The progress lines and the downloaded file are read from yt-dlp's output
+    "-q",
+    "--quiet",
+    "--no-progress",
+    "--print",
+    "-j",
+    "--dump-json",
+    "-J",
+    "--dump-single-json",
+    "-s",
+    "--simulate",
+    // Would run commands or load options and URLs that are never checked
+    "--exec",
+    "--exec-before-download",
+    "--use-postprocessor",
+    "--netrc-cmd",
+    "--downloader",
+    "--external-downloader",
+    "--downloader-args",
+    "--external-downloader-args",
+    "--plugin-dirs",
+    "--config-locations",
+    "-a",
+    "--batch-file",
+    "-r",
+    "--limit-rate",
+    "--proxy",
+    "--cookies",
+    "--cookies-from-browser",
+    "--user-agent",
+    "--add-header",
+    "--ffmpeg-location",
+];
+
+// Short options that take a value, which may be attached: in "-ko%(id)s" the
+// "-o" option gets "%(id)s"
+const SHORT_OPTIONS_WITH_VALUE: &str = "afopruINPRSt2";
+
+// Check extra yt-dlp arguments against the options the application controls
+pub fn validate_extra_args(args: &[String]) -> Result<(), String> {
+    for arg in args {
+        let option = arg
+            .split_once('=')
+            .map_or(arg.as_str(), |(option, _)| option);
+        let controlled = if option.starts_with("--") {
+            // yt-dlp also accepts unambiguous abbreviations of long options
+            option.len() > 2
+                && CONTROLLED_OPTIONS
+                    .iter()
+                    .any(|controlled| controlled.starts_with(option))
+        } else if let Some(letters) = arg.strip_prefix('-') {
+            // Short options can be combined, e.g. "-kq" is "-k -q"
+            letters
+                .chars()
+                .scan(false, |value_started, letter| {
+                    let is_option = !*value_started;
+                    *value_started |= SHORT_OPTIONS_WITH_VALUE.contains(letter);
+                    Some((letter, is_option))
+                })
+                .any(|(letter, is_option)| {
+                    is_option && CONTROLLED_OPTIONS.contains(&format!("-{}", letter).as_str())
+                })
+        } else {
+            false
+        };
+        if controlled {
+            return Err(format!(
+                "The yt-dlp option {} is controlled by the application and can't be used in extra_args",
+                option
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn controlled_options_are_rejected() {
+        assert!(validate_extra_args(&args(&["--sponsorblock-remove", "all"])).is_ok());
+        assert!(validate_extra_args(&args(&["--match-filter", "duration < 600"])).is_ok());
+        assert!(validate_extra_args(&args(&["--geo-bypass", "-S", "abr"])).is_ok());
+
+        assert!(validate_extra_args(&args(&["-o", "%(id)s"])).is_err());
+        assert!(validate_extra_args(&args(&["--output=%(id)s"])).is_err());
+        assert!(validate_extra_args(&args(&["-o%(id)s"])).is_err());
+        assert!(validate_extra_args(&args(&["--audio-format", "flac"])).is_err());
+        for option in ["-q", "--no-progress", "--print=title", "-J", "-s", "--exec"] {
+            assert!(validate_extra_args(&args(&[option])).is_err(), "{}", option);
+        }
+        assert!(validate_extra_args(&args(&["--config-locations", "/tmp/yt-dlp.conf"])).is_err());
+
+        // Aliases and abbreviations
+        for option in [
+            "--exec-before-download",
+            "--exec-before",
+            "--outp",
+            "-a",
+            "--batch-file",
+        ] {
+            assert!(
+                validate_extra_args(&args(&[option, "x"])).is_err(),
+                "{}",
+                option
+            );
+        }
+        // Combined short options, only the letters before a value are options
+        for option in ["-ko%(id)s", "-kq", "-wxk", "-ka"] {
+            assert!(validate_extra_args(&args(&[option])).is_err(), "{}", option);
+        }
+        assert!(validate_extra_args(&args(&["-fbestaudio", "-kw", "-Sabr,ext"])).is_ok());
+    }
+
+    #[test]
+    fn subtitles_are_requested_for_the_selected_languages() {
+        assert!(SubtitleSettings::default().args().is_empty());
+
+        let mut subtitles = SubtitleSettings {
+            languages: args(&["en", "hu"]),
+            auto_generated: true,
+            format: Some(SubtitleFormat::Srt),
+            embed: false,
+        };
+        assert_eq!(
+            subtitles.args(),
+            args(&[
+                "--write-subs",
+                "--write-auto-subs",
+                "--sub-langs",
+                "en,hu",
+                "--convert-subs",
+                "srt"
+            ])
+        );
+
+        subtitles.embed = true;
+        assert_eq!(subtitles.args().last().unwrap(), "--embed-subs");
+        assert!(subtitles.args().contains(&"--write-subs".to_string()));
+    }
+}
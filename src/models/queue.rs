@@ -0,0 +1,45 @@
+use std::{collections::VecDeque, fs, path::Path};
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::music::Music;
+
+// The download queue left over when the application quits, restored on the
+// next start so nothing queued is lost
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct SavedQueue {
+    pub videos: VecDeque<Music>,
+}
+
+const QUEUE_PATH: &str = "download_queue.json";
+
+impl SavedQueue {
+    pub fn load() -> Self {
+        if !Path::new(QUEUE_PATH).exists() {
+            return Self::default();
+        }
+        match fs::read_to_string(QUEUE_PATH) {
+            Ok(content) => match serde_json::from_str::<SavedQueue>(&content) {
+                Ok(queue) => queue,
+                Err(e) => {
+                    println!("Error parsing queue file: {}", e);
+                    Self::default()
+                }
+            },
+            Err(_) => Self::default(),
+        }
+    }
+
+    // An empty queue removes the file instead of leaving an empty one behind
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        if self.videos.is_empty() {
+            if Path::new(QUEUE_PATH).exists() {
+                fs::remove_file(QUEUE_PATH)?;
+            }
+            return Ok(());
+        }
+        let json = serde_json::to_string_pretty(self)?;
+        fs::write(QUEUE_PATH, json)?;
+        Ok(())
+    }
+}
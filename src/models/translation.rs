@@ -1,14 +1,110 @@
+use chrono::{DateTime, FixedOffset};
 use lazy_static::lazy_static;
-use std::collections::HashMap;
+use serde::Deserialize;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
 use std::fs;
 use std::path::Path;
 use std::process;
 use std::sync::Mutex;
+use std::time::Duration;
 
 use crate::app_config::Config;
-use crate::models::language::Language;
+use crate::models::language::{Language, PluralCategory};
 
-pub type TranslationMap = HashMap<String, String>;
+pub type TranslationMap = HashMap<String, TranslationEntry>;
+
+// A single entry of a language file: either a plain string or a table of
+// plural forms keyed by CLDR category (`key.one = "..."`, `key.other = "..."`)
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum TranslationEntry {
+    Text(String),
+    Plural(HashMap<String, String>),
+}
+
+impl TranslationEntry {
+    // Pick the form for the given plural category, falling back to "other"
+    fn select(&self, category: PluralCategory) -> Option<&String> {
+        match self {
+            TranslationEntry::Text(text) => Some(text),
+            TranslationEntry::Plural(forms) => forms
+                .get(category.as_str())
+                .or_else(|| forms.get(PluralCategory::Other.as_str())),
+        }
+    }
+}
+
+// Value substituted for a named `{placeholder}`. Numbers and dates are
+// formatted according to the current language.
+#[derive(Debug, Clone)]
+pub enum TranslationArg {
+    Text(String),
+    Number(i64),
+    Date(DateTime<FixedOffset>),
+    Duration(Duration),
+}
+
+impl From<&str> for TranslationArg {
+    fn from(value: &str) -> Self {
+        TranslationArg::Text(value.to_string())
+    }
+}
+
+impl From<String> for TranslationArg {
+    fn from(value: String) -> Self {
+        TranslationArg::Text(value)
+    }
+}
+
+impl From<&String> for TranslationArg {
+    fn from(value: &String) -> Self {
+        TranslationArg::Text(value.clone())
+    }
+}
+
+impl From<usize> for TranslationArg {
+    fn from(value: usize) -> Self {
+        TranslationArg::Number(value as i64)
+    }
+}
+
+impl From<u64> for TranslationArg {
+    fn from(value: u64) -> Self {
+        TranslationArg::Number(value as i64)
+    }
+}
+
+impl From<i64> for TranslationArg {
+    fn from(value: i64) -> Self {
+        TranslationArg::Number(value)
+    }
+}
+
+impl From<DateTime<FixedOffset>> for TranslationArg {
+    fn from(value: DateTime<FixedOffset>) -> Self {
+        TranslationArg::Date(value)
+    }
+}
+
+impl From<Duration> for TranslationArg {
+    fn from(value: Duration) -> Self {
+        TranslationArg::Duration(value)
+    }
+}
+
+impl TranslationArg {
+    fn format(&self, language: Language) -> String {
+        match self {
+            TranslationArg::Text(text) => text.clone(),
+            TranslationArg::Number(number) => language.format_number(*number),
+            TranslationArg::Date(date) => language.format_date(date),
+            TranslationArg::Duration(duration) => language.format_duration(*duration),
+        }
+    }
+}
+
+pub type TranslationArgs<'a> = [(&'a str, TranslationArg)];
 
 // ANSI color codes enum
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -82,7 +178,7 @@ pub struct Translations {
 impl Translations {
     pub fn new() -> Self {
         let mut translations = Self {
-            current_language: Language::Hungarian,
+            current_language: Language::English,
             strings: HashMap::new(),
             coloring: false,
         };
@@ -110,16 +206,18 @@ impl Translations {
 
     pub fn init(config: &Config) {
         let mut translations = TRANSLATIONS.lock().unwrap();
-        translations.current_language = config.language.clone();
+        translations.current_language = config.language;
+        translations.coloring = config.coloring;
     }
 
-    pub fn get(key: &str) -> String {
+    // Look up the form of `key` for the given plural category
+    pub fn get(key: &str, category: PluralCategory) -> String {
         let translations = TRANSLATIONS.lock().unwrap();
 
         let mut result = String::new();
 
         if let Some(lang_map) = translations.strings.get(&translations.current_language) {
-            if let Some(text) = lang_map.get(key) {
+            if let Some(text) = lang_map.get(key).and_then(|entry| entry.select(category)) {
                 result = text.clone();
             }
         }
@@ -127,7 +225,7 @@ impl Translations {
         // Fall back to English if the key doesn't exist in current language
         if result.is_empty() && translations.current_language != Language::English {
             if let Some(eng_map) = translations.strings.get(&Language::English) {
-                if let Some(text) = eng_map.get(key) {
+                if let Some(text) = eng_map.get(key).and_then(|entry| entry.select(category)) {
                     result = text.clone();
                 }
             }
@@ -139,7 +237,7 @@ impl Translations {
         }
 
         // Apply color formatting
-        apply_color_formatting(&result)
+        apply_color_formatting(&result, translations.coloring)
     }
 
     pub fn change_language(language: Language) {
@@ -152,16 +250,271 @@ impl Translations {
         translations.coloring = coloring;
     }
 
-    pub fn t(key: &str, args: Option<&[&str]>) -> String {
-        Self::get(&replace_all(key, args))
+    // Compare every bundled language file against English and report missing or
+    // extra keys, placeholder mismatches and malformed color tags
+    pub fn validate() -> Result<Vec<TranslationIssue>, Box<dyn std::error::Error>> {
+        let english = load_language_file(&Language::English.to_filename())?;
+        let mut issues = Vec::new();
+
+        for language in Language::all() {
+            let map = load_language_file(&language.to_filename())?;
+
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+
+            for key in &keys {
+                let entry = &map[*key];
+
+                for tag in invalid_color_tags(entry) {
+                    issues.push(TranslationIssue::InvalidColorTag {
+                        language,
+                        key: key.to_string(),
+                        tag,
+                    });
+                }
+
+                match english.get(*key) {
+                    Some(english_entry) => {
+                        let expected = entry_placeholders(english_entry);
+                        let found = entry_placeholders(entry);
+                        if expected != found {
+                            issues.push(TranslationIssue::PlaceholderMismatch {
+                                language,
+                                key: key.to_string(),
+                                expected,
+                                found,
+                            });
+                        }
+                    }
+                    None => issues.push(TranslationIssue::ExtraKey {
+                        language,
+                        key: key.to_string(),
+                    }),
+                }
+            }
+
+            let mut missing: Vec<&String> = english
+                .keys()
+                .filter(|key| !map.contains_key(*key))
+                .collect();
+            missing.sort();
+            for key in missing {
+                issues.push(TranslationIssue::MissingKey {
+                    language,
+                    key: key.clone(),
+                });
+            }
+        }
+
+        for (key, location) in used_keys(Path::new("src"))? {
+            if !english.contains_key(&key) {
+                issues.push(TranslationIssue::UnknownKey { key, location });
+            }
+        }
+
+        Ok(issues)
+    }
+
+    pub fn current_language() -> Language {
+        TRANSLATIONS.lock().unwrap().current_language
+    }
+
+    // Translate `key`, substituting named `{placeholders}` from `args`.
+    // A numeric `count` argument selects the plural form of the entry.
+    pub fn t(key: &str, args: Option<&TranslationArgs>) -> String {
+        let args = args.unwrap_or_default();
+        let language = Self::current_language();
+
+        let category = args
+            .iter()
+            .find_map(|(name, arg)| match (*name, arg) {
+                ("count", TranslationArg::Number(count)) => Some(language.plural_category(*count)),
+                _ => None,
+            })
+            .unwrap_or(PluralCategory::Other);
+
+        replace_all(&Self::get(key, category), args, language)
     }
 }
 
-// Apply color formatting to text with XML-like color tags
-fn apply_color_formatting(text: &str) -> String {
-    let config = Config::load();
+// A problem found in a language file by `Translations::validate`
+#[derive(Debug, Clone, PartialEq)]
+pub enum TranslationIssue {
+    MissingKey {
+        language: Language,
+        key: String,
+    },
+    ExtraKey {
+        language: Language,
+        key: String,
+    },
+    PlaceholderMismatch {
+        language: Language,
+        key: String,
+        expected: BTreeSet<String>,
+        found: BTreeSet<String>,
+    },
+    InvalidColorTag {
+        language: Language,
+        key: String,
+        tag: String,
+    },
+    // Used in the source code, but not in the English file
+    UnknownKey {
+        key: String,
+        location: String,
+    },
+}
+
+impl fmt::Display for TranslationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TranslationIssue::MissingKey { language, key } => {
+                write!(f, "[{}] missing key '{}'", language, key)
+            }
+            TranslationIssue::ExtraKey { language, key } => {
+                write!(
+                    f,
+                    "[{}] extra key '{}' not present in English",
+                    language, key
+                )
+            }
+            TranslationIssue::PlaceholderMismatch {
+                language,
+                key,
+                expected,
+                found,
+            } => write!(
+                f,
+                "[{}] placeholders of '{}' differ: expected {:?}, found {:?}",
+                language, key, expected, found
+            ),
+            TranslationIssue::InvalidColorTag { language, key, tag } => {
+                write!(f, "[{}] invalid color tag '{}' in '{}'", language, tag, key)
+            }
+            TranslationIssue::UnknownKey { key, location } => {
+                write!(
+                    f,
+                    "key '{}' used in {} is missing from English",
+                    key, location
+                )
+            }
+        }
+    }
+}
+
+// Keys passed as literals to `Translations::t` in the Rust files under `dir`,
+// with the file and line they are used on. Nothing is found when the sources
+// aren't there, e.g. next to an installed binary.
+fn used_keys(dir: &Path) -> Result<Vec<(String, String)>, Box<dyn std::error::Error>> {
+    const CALL: &str = "Translations::t(";
+
+    let mut keys = Vec::new();
+    if !dir.is_dir() {
+        return Ok(keys);
+    }
+    let mut entries: Vec<_> = fs::read_dir(dir)?.collect::<Result<_, _>>()?;
+    entries.sort_by_key(|entry| entry.path());
+    for entry in entries {
+        let path = entry.path();
+        if path.is_dir() {
+            keys.extend(used_keys(&path)?);
+            continue;
+        }
+        if path.extension().is_none_or(|extension| extension != "rs") {
+            continue;
+        }
+
+        let source = fs::read_to_string(&path)?;
+        for (start, _) in source.match_indices(CALL) {
+            // The key may be on the next line when the call is wrapped
+            let Some(rest) = source[start + CALL.len()..].trim_start().strip_prefix('"') else {
+                continue;
+            };
+            let Some(key) = rest.split('"').next().filter(|key| is_key(key)) else {
+                continue;
+            };
+            let line = source[..start].matches('\n').count() + 1;
+            keys.push((key.to_string(), format!("{}:{}", path.display(), line)));
+        }
+    }
+    Ok(keys)
+}
+
+// Keys are made of letters, digits, underscores and dots
+fn is_key(text: &str) -> bool {
+    !text.is_empty()
+        && text
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+}
+
+// Collect the placeholder names used by all forms of an entry
+fn entry_placeholders(entry: &TranslationEntry) -> BTreeSet<String> {
+    let forms: Vec<&String> = match entry {
+        TranslationEntry::Text(text) => vec![text],
+        TranslationEntry::Plural(forms) => forms.values().collect(),
+    };
+
+    let mut names = BTreeSet::new();
+    for form in forms {
+        let mut rest = form.as_str();
+        while let Some(start) = rest.find('{') {
+            let after = &rest[start + 1..];
+            match after.find('}') {
+                Some(end) => {
+                    names.insert(after[..end].to_string());
+                    rest = &after[end + 1..];
+                }
+                None => break,
+            }
+        }
+    }
+    names
+}
+
+// Find unknown color tags and closing tags without a matching opening tag.
+// Tags left open at the end are allowed, prompts use them to color the input.
+fn invalid_color_tags(entry: &TranslationEntry) -> Vec<String> {
+    let forms: Vec<&String> = match entry {
+        TranslationEntry::Text(text) => vec![text],
+        TranslationEntry::Plural(forms) => forms.values().collect(),
+    };
+
+    let mut invalid = Vec::new();
+    for form in forms {
+        let mut open_tags: Vec<&str> = Vec::new();
+        let mut rest = form.as_str();
+        while let Some(start) = rest.find('<') {
+            let after = &rest[start + 1..];
+            let Some(end) = after.find('>') else {
+                break;
+            };
+            let tag = &after[..end];
+            let tag_name = tag.strip_prefix('/').unwrap_or(tag);
+
+            if !COLOR_TAGS.contains_key(tag_name) {
+                invalid.push(format!("<{}>", tag));
+            } else if tag.starts_with('/') {
+                match open_tags.iter().rposition(|open| *open == tag_name) {
+                    Some(pos) => {
+                        open_tags.remove(pos);
+                    }
+                    None => invalid.push(format!("<{}>", tag)),
+                }
+            } else {
+                open_tags.push(tag_name);
+            }
 
-    if !config.coloring {
+            rest = &after[end + 1..];
+        }
+    }
+    invalid
+}
+
+// Apply color formatting to text with XML-like color tags
+fn apply_color_formatting(text: &str, coloring: bool) -> String {
+    if !coloring {
         // Remove all tags
         let mut result = text.to_string();
         for (tag, _) in COLOR_TAGS.iter() {
@@ -232,7 +585,20 @@ fn load_language_file(filename: &str) -> Result<TranslationMap, Box<dyn std::err
     let path = Path::new("languages").join(filename);
     if path.exists() {
         let content = fs::read_to_string(path)?;
-        let parsed: HashMap<String, String> = toml::from_str(&content)?;
+        let parsed: TranslationMap = toml::from_str(&content)?;
+
+        for (key, entry) in &parsed {
+            if let TranslationEntry::Plural(forms) = entry {
+                for category in forms.keys() {
+                    category
+                        .parse::<PluralCategory>()
+                        .map_err(|e| format!("{} in '{}'", e, key))?;
+                }
+                if !forms.contains_key(PluralCategory::Other.as_str()) {
+                    return Err(format!("Missing 'other' plural form for '{}'", key).into());
+                }
+            }
+        }
 
         Ok(parsed)
     } else {
@@ -244,22 +610,97 @@ fn load_language_file(filename: &str) -> Result<TranslationMap, Box<dyn std::err
     }
 }
 
-fn replace_all(template: &str, replacements: Option<&[&str]>) -> String {
+// Replace every `{name}` placeholder with its formatted argument.
+// Unknown placeholders are left untouched.
+fn replace_all(template: &str, args: &TranslationArgs, language: Language) -> String {
     let mut result = String::new();
-    let mut parts = template.split("{}");
-    let mut iter = replacements.into_iter().flatten();
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+
+        match after.find('}') {
+            Some(end) => {
+                let name = &after[..end];
+                match args.iter().find(|(arg_name, _)| *arg_name == name) {
+                    Some((_, arg)) => result.push_str(&arg.format(language)),
+                    None => {
+                        result.push('{');
+                        result.push_str(name);
+                        result.push('}');
+                    }
+                }
+                rest = &after[end + 1..];
+            }
+            None => {
+                result.push_str(&rest[start..]);
+                rest = "";
+            }
+        }
+    }
 
-    if let Some(first) = parts.next() {
-        result.push_str(first);
+    result.push_str(rest);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_language_files_are_complete() {
+        let issues = Translations::validate().expect("language files should load");
+        let report: Vec<String> = issues.iter().map(|issue| issue.to_string()).collect();
+        assert!(issues.is_empty(), "{}", report.join("\n"));
     }
 
-    for part in parts {
-        match iter.next() {
-            Some(value) => result.push_str(value),
-            None => result.push_str("{}"),
-        }
-        result.push_str(part);
+    #[test]
+    fn validate_detects_broken_entries() {
+        let entry = TranslationEntry::Text("<red>{count} items</blue> <bold>".to_string());
+        assert_eq!(invalid_color_tags(&entry), vec!["</blue>", "<bold>"]);
+
+        let plural = TranslationEntry::Plural(HashMap::from([
+            ("one".to_string(), "{count} item".to_string()),
+            ("other".to_string(), "{count} items in {dir}".to_string()),
+        ]));
+        assert_eq!(
+            entry_placeholders(&plural),
+            BTreeSet::from(["count".to_string(), "dir".to_string()])
+        );
+
+        let keys: Vec<String> = used_keys(Path::new("src/views"))
+            .unwrap()
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect();
+        assert!(keys.iter().any(|key| key == "coloring_toggled"));
     }
 
-    result
+    #[test]
+    fn placeholders_are_replaced_by_name() {
+        let args: Vec<(&str, TranslationArg)> = vec![
+            ("dir", "/music".into()),
+            ("count", 12345usize.into()),
+            ("duration", Duration::from_secs(65).into()),
+        ];
+        assert_eq!(
+            replace_all(
+                "{count} files in {dir}, {duration}",
+                &args,
+                Language::English
+            ),
+            "12,345 files in /music, 1m 05s"
+        );
+        assert_eq!(
+            replace_all("{dir}: {count}", &args, Language::Hungarian),
+            "/music: 12\u{a0}345"
+        );
+
+        // Missing arguments and unclosed braces stay as they are
+        assert_eq!(
+            replace_all("{title} in {dir} {", &args, Language::English),
+            "{title} in /music {"
+        );
+    }
 }
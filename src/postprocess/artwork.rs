@@ -0,0 +1,324 @@
+use std::{
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use base64::Engine;
+use id3::{
+    frame::{Picture, PictureType},
+    TagLike, Version,
+};
+use image::{imageops::FilterType, DynamicImage, ImageFormat};
+use serde::{Deserialize, Serialize};
+
+use super::{ffmpeg_error, ffmpeg_rewrite, tags};
+use crate::installer::ffmpeg_command;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ArtworkFormat {
+    #[default]
+    Jpg,
+    Png,
+}
+
+impl ArtworkFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ArtworkFormat::Jpg => "jpg",
+            ArtworkFormat::Png => "png",
+        }
+    }
+
+    fn mime_type(&self) -> &'static str {
+        match self {
+            ArtworkFormat::Jpg => "image/jpeg",
+            ArtworkFormat::Png => "image/png",
+        }
+    }
+}
+
+// How the video thumbnail is turned into album art, part of a download profile.
+// With everything unset yt-dlp embeds the thumbnail as it is.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ArtworkSettings {
+    // Crop the thumbnail to a square around its center, which cuts off the
+    // black bars of 16:9 thumbnails
+    pub square: bool,
+    // Larger thumbnails are scaled down to fit this many pixels per side
+    pub max_size: Option<u32>,
+    // Image format of the artwork, JPEG unless set
+    pub format: Option<ArtworkFormat>,
+    // Also save the artwork as "cover.jpg" in album and playlist folders
+    pub cover_file: bool,
+}
+
+impl ArtworkSettings {
+    pub fn is_enabled(&self) -> bool {
+        self.square || self.max_size.is_some() || self.format.is_some() || self.cover_file
+    }
+
+    pub fn format(&self) -> ArtworkFormat {
+        self.format.unwrap_or_default()
+    }
+
+    // yt-dlp arguments that keep the thumbnail as a file in the artwork
+    // format instead of embedding it
+    pub fn args(&self) -> Vec<String> {
+        match self.is_enabled() {
+            true => vec![
+                "--no-embed-thumbnail".to_string(),
+                "--write-thumbnail".to_string(),
+                "--convert-thumbnails".to_string(),
+                self.format().extension().to_string(),
+            ],
+            false => Vec::new(),
+        }
+    }
+}
+
+// Processed thumbnail, ready to be embedded
+pub struct Artwork {
+    pub path: PathBuf,
+    pub format: ArtworkFormat,
+    pub width: u32,
+    pub height: u32,
+}
+
+fn crop_square(image: DynamicImage) -> DynamicImage {
+    let side = image.width().min(image.height());
+    image.crop_imm(
+        (image.width() - side) / 2,
+        (image.height() - side) / 2,
+        side,
+        side,
+    )
+}
+
+fn transform(mut image: DynamicImage, settings: &ArtworkSettings) -> DynamicImage {
+    if settings.square {
+        image = crop_square(image);
+    }
+    if let Some(size) = settings.max_size {
+        if image.width() > size || image.height() > size {
+            image = image.resize(size, size, FilterType::Lanczos3);
+        }
+    }
+    image
+}
+
+// Crop and scale the thumbnail yt-dlp wrote, in place
+pub fn process(
+    thumbnail: &Path,
+    settings: &ArtworkSettings,
+) -> Result<Artwork, Box<dyn std::error::Error>> {
+    let image = transform(image::open(thumbnail)?, settings);
+    let format = settings.format();
+
+    match format {
+        // JPEG has no alpha channel
+        ArtworkFormat::Jpg => DynamicImage::ImageRgb8(image.to_rgb8())
+            .save_with_format(thumbnail, ImageFormat::Jpeg)?,
+        ArtworkFormat::Png => image.save_with_format(thumbnail, ImageFormat::Png)?,
+    }
+
+    Ok(Artwork {
+        path: thumbnail.to_path_buf(),
+        format,
+        width: image.width(),
+        height: image.height(),
+    })
+}
+
+// Replace the artwork of an audio file. MP3 files get an ID3 picture frame,
+// Ogg files a Vorbis picture comment and other formats an attached picture
+// stream.
+pub fn embed(file: &Path, artwork: &Artwork) -> Result<(), Box<dyn std::error::Error>> {
+    let extension = file
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    match extension.as_str() {
+        "mp3" => {
+            let mut tag = tags::id3_tag(file)?;
+            tag.remove_all_pictures();
+            tag.add_frame(Picture {
+                mime_type: artwork.format.mime_type().to_string(),
+                picture_type: PictureType::CoverFront,
+                description: String::new(),
+                data: fs::read(&artwork.path)?,
+            });
+            tag.write_to_path(file, Version::Id3v24)?;
+            Ok(())
+        }
+        "opus" | "ogg" | "oga" => embed_vorbis_picture(file, artwork),
+        _ => ffmpeg_rewrite(
+            file,
+            &[
+                "-i".into(),
+                artwork.path.clone().into(),
+                "-map".into(),
+                "0:a".into(),
+                "-map".into(),
+                "1".into(),
+                "-c".into(),
+                "copy".into(),
+                "-disposition:v:0".into(),
+                "attached_pic".into(),
+            ],
+        ),
+    }
+}
+
+// Ogg can't hold picture streams, the artwork goes into a
+// METADATA_BLOCK_PICTURE comment holding a FLAC picture block. The comment is
+// too long for the command line, so the tags go through a metadata file.
+fn embed_vorbis_picture(file: &Path, artwork: &Artwork) -> Result<(), Box<dyn std::error::Error>> {
+    let output = Command::new(ffmpeg_command())
+        .args(["-v", "error", "-i"])
+        .arg(file)
+        .args(["-f", "ffmetadata", "-"])
+        .output()?;
+    if !output.status.success() {
+        return Err(ffmpeg_error(file, &output.stderr));
+    }
+
+    let mut metadata: String = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|line| !line.to_uppercase().starts_with("METADATA_BLOCK_PICTURE="))
+        .map(|line| format!("{}\n", line))
+        .collect();
+    let block = base64::engine::general_purpose::STANDARD
+        .encode(picture_block(artwork, &fs::read(&artwork.path)?));
+    // "=" has to be escaped in ffmetadata values
+    metadata.push_str(&format!(
+        "METADATA_BLOCK_PICTURE={}\n",
+        block.replace('=', "\\=")
+    ));
+
+    let metadata_file = file.with_extension("ffmetadata");
+    fs::write(&metadata_file, metadata)?;
+    let args: [OsString; 8] = [
+        "-i".into(),
+        metadata_file.clone().into(),
+        "-map".into(),
+        "0:a".into(),
+        "-map_metadata".into(),
+        "1".into(),
+        "-c".into(),
+        "copy".into(),
+    ];
+    let result = ffmpeg_rewrite(file, &args);
+    let _ = fs::remove_file(&metadata_file);
+    result
+}
+
+// FLAC picture metadata block, all numbers big-endian
+fn picture_block(artwork: &Artwork, data: &[u8]) -> Vec<u8> {
+    let mime_type = artwork.format.mime_type().as_bytes();
+    let mut block = Vec::with_capacity(32 + mime_type.len() + data.len());
+    // Front cover
+    block.extend_from_slice(&3u32.to_be_bytes());
+    block.extend_from_slice(&(mime_type.len() as u32).to_be_bytes());
+    block.extend_from_slice(mime_type);
+    // No description
+    block.extend_from_slice(&0u32.to_be_bytes());
+    block.extend_from_slice(&artwork.width.to_be_bytes());
+    block.extend_from_slice(&artwork.height.to_be_bytes());
+    // Color depth, and no indexed colors
+    block.extend_from_slice(&24u32.to_be_bytes());
+    block.extend_from_slice(&0u32.to_be_bytes());
+    block.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    block.extend_from_slice(data);
+    block
+}
+
+// Save the artwork as the cover of a folder, unless it already has one. It is
+// always "cover.jpg", the name players look for, also when the embedded art
+// is a PNG.
+pub fn write_cover(folder: &Path, artwork: &Artwork) -> Result<(), Box<dyn std::error::Error>> {
+    let cover = folder.join("cover.jpg");
+    if cover.exists() {
+        return Ok(());
+    }
+    match artwork.format {
+        ArtworkFormat::Jpg => {
+            fs::copy(&artwork.path, cover)?;
+        }
+        // JPEG has no transparency
+        ArtworkFormat::Png => DynamicImage::ImageRgb8(image::open(&artwork.path)?.to_rgb8())
+            .save_with_format(cover, ImageFormat::Jpeg)?,
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn thumbnails_are_cropped_to_the_center_and_scaled_down() {
+        let mut image = image::RgbImage::new(1280, 720);
+        // Mark the center, it has to survive the crop
+        image.put_pixel(640, 360, image::Rgb([255, 0, 0]));
+        let settings = ArtworkSettings {
+            square: true,
+            ..ArtworkSettings::default()
+        };
+
+        let cropped = transform(DynamicImage::ImageRgb8(image.clone()), &settings);
+        assert_eq!((cropped.width(), cropped.height()), (720, 720));
+        assert_eq!(
+            cropped.to_rgb8().get_pixel(360, 360),
+            &image::Rgb([255, 0, 0])
+        );
+
+        let settings = ArtworkSettings {
+            max_size: Some(500),
+            ..settings
+        };
+        let scaled = transform(DynamicImage::ImageRgb8(image.clone()), &settings);
+        assert_eq!((scaled.width(), scaled.height()), (500, 500));
+
+        // Smaller images aren't scaled up
+        let settings = ArtworkSettings {
+            square: false,
+            max_size: Some(2000),
+            ..settings
+        };
+        let kept = transform(DynamicImage::ImageRgb8(image), &settings);
+        assert_eq!((kept.width(), kept.height()), (1280, 720));
+    }
+
+    #[test]
+    fn png_artwork_is_saved_as_a_jpeg_cover() {
+        let dir =
+            std::env::temp_dir().join(format!("youtube_downloader_cover_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("thumbnail.png");
+        image::RgbaImage::new(4, 4).save(&path).unwrap();
+        let artwork = Artwork {
+            path,
+            format: ArtworkFormat::Png,
+            width: 4,
+            height: 4,
+        };
+
+        write_cover(&dir, &artwork).unwrap();
+        assert_eq!(
+            image::ImageReader::open(dir.join("cover.jpg"))
+                .unwrap()
+                .with_guessed_format()
+                .unwrap()
+                .format(),
+            Some(ImageFormat::Jpeg)
+        );
+        assert!(!dir.join("cover.png").exists());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
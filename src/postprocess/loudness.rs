@@ -0,0 +1,321 @@
+use std::{path::Path, process::Command};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use super::{ffmpeg_error, ffmpeg_rewrite, tags};
+use crate::installer::ffmpeg_command;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LoudnessMode {
+    // Change the audio to the target loudness with ffmpeg's EBU R128 filter
+    Normalize,
+    // Keep the audio and write ReplayGain tags for players to apply
+    ReplayGain,
+}
+
+// Loudness handling of a download profile
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LoudnessSettings {
+    // Unset leaves the loudness alone
+    pub mode: Option<LoudnessMode>,
+    // Integrated loudness to normalize to, in LUFS
+    pub target: f64,
+    // Highest true peak after normalizing, in dBTP
+    pub true_peak: f64,
+    // Loudness range to normalize to, in LU
+    pub range: f64,
+}
+
+impl Default for LoudnessSettings {
+    fn default() -> Self {
+        Self {
+            mode: None,
+            target: -14.0,
+            true_peak: -1.0,
+            range: 11.0,
+        }
+    }
+}
+
+// Loudness of a download as measured before it was changed, stored in the
+// history
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Loudness {
+    // Integrated loudness in LUFS
+    pub integrated: f64,
+    // In dBTP
+    pub true_peak: f64,
+    // Loudness range in LU
+    pub range: f64,
+    // Gate threshold of the measurement in LUFS
+    pub threshold: f64,
+    // Gain applied, or written as ReplayGain track gain, in dB
+    pub gain: f64,
+}
+
+// Reference loudness of ReplayGain 2.0
+const REPLAYGAIN_REFERENCE: f64 = -18.0;
+
+// Reference loudness of Opus' R128 gain tags
+const OPUS_REFERENCE: f64 = -23.0;
+
+// Stream details the normalized file is encoded with
+struct Measurement {
+    loudness: Loudness,
+    sample_rate: Option<String>,
+    bitrate: Option<String>,
+}
+
+// Values printed by the loudnorm filter, as strings
+#[derive(Deserialize)]
+struct LoudnormOutput {
+    input_i: String,
+    input_tp: String,
+    input_lra: String,
+    input_thresh: String,
+}
+
+// Measure a file with a first loudnorm pass. Silent files have no loudness and
+// give None.
+fn measure(
+    file: &Path,
+    settings: &LoudnessSettings,
+) -> Result<Option<Measurement>, Box<dyn std::error::Error>> {
+    let output = Command::new(ffmpeg_command())
+        .args(["-hide_banner", "-nostats", "-i"])
+        .arg(file)
+        .args(["-map", "0:a:0", "-af"])
+        .arg(format!(
+            "loudnorm=I={}:TP={}:LRA={}:print_format=json",
+            settings.target, settings.true_peak, settings.range
+        ))
+        .args(["-f", "null", "-"])
+        .output()?;
+    if !output.status.success() {
+        return Err(ffmpeg_error(file, &output.stderr));
+    }
+
+    parse_measurement(&String::from_utf8_lossy(&output.stderr), settings)
+}
+
+fn parse_measurement(
+    stderr: &str,
+    settings: &LoudnessSettings,
+) -> Result<Option<Measurement>, Box<dyn std::error::Error>> {
+    // The JSON block comes last, after the stream details
+    let start = stderr
+        .rfind('{')
+        .ok_or("ffmpeg didn't print the loudness measurement")?;
+    let end = stderr[start..]
+        .find('}')
+        .ok_or("ffmpeg didn't print the loudness measurement")?;
+    let values: LoudnormOutput = serde_json::from_str(&stderr[start..=start + end])?;
+
+    let parse = |value: &str| value.trim().parse::<f64>().ok().filter(|v| v.is_finite());
+    let (Some(integrated), Some(true_peak), Some(range), Some(threshold)) = (
+        parse(&values.input_i),
+        parse(&values.input_tp),
+        parse(&values.input_lra),
+        parse(&values.input_thresh),
+    ) else {
+        return Ok(None);
+    };
+
+    let gain = match settings.mode {
+        Some(LoudnessMode::ReplayGain) => REPLAYGAIN_REFERENCE - integrated,
+        _ => settings.target - integrated,
+    };
+    // loudnorm prints two decimals
+    let gain = (gain * 100.0).round() / 100.0;
+
+    // e.g. "Stream #0:0: Audio: mp3, 44100 Hz, stereo, fltp, 128 kb/s"
+    let stream = stderr
+        .lines()
+        .find(|line| line.contains("Stream #") && line.contains("Audio:"));
+    let capture = |pattern: &str| {
+        let regex = Regex::new(pattern).ok()?;
+        Some(regex.captures(stream?)?.get(1)?.as_str().to_string())
+    };
+
+    Ok(Some(Measurement {
+        loudness: Loudness {
+            integrated,
+            true_peak,
+            range,
+            threshold,
+            gain,
+        },
+        sample_rate: capture(r"(\d+) Hz"),
+        bitrate: capture(r"(\d+) kb/s"),
+    }))
+}
+
+// Second loudnorm pass with the measured values, which lets the filter apply a
+// single gain instead of compressing the dynamics
+fn normalize(
+    file: &Path,
+    measured: &Measurement,
+    settings: &LoudnessSettings,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let loudness = &measured.loudness;
+    let filter = format!(
+        "loudnorm=I={}:TP={}:LRA={}:measured_I={}:measured_TP={}:measured_LRA={}:measured_thresh={}:linear=true",
+        settings.target,
+        settings.true_peak,
+        settings.range,
+        loudness.integrated,
+        loudness.true_peak,
+        loudness.range,
+        loudness.threshold
+    );
+
+    let mut args = vec![
+        "-map".into(),
+        "0".into(),
+        "-c:v".into(),
+        "copy".into(),
+        "-af".into(),
+        filter.into(),
+    ];
+    // loudnorm resamples to 192 kHz, the file keeps its own rate and bitrate
+    if let Some(sample_rate) = &measured.sample_rate {
+        args.push("-ar".into());
+        args.push(sample_rate.into());
+    }
+    if let Some(bitrate) = &measured.bitrate {
+        args.push("-b:a".into());
+        args.push(format!("{}k", bitrate).into());
+    }
+    ffmpeg_rewrite(file, &args)
+}
+
+// Tags for a track and, for tracks split from an album, the whole album
+fn gain_tags(
+    file: &Path,
+    track: &Loudness,
+    album: Option<&Loudness>,
+) -> Vec<(&'static str, String)> {
+    let is_opus = file
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("opus"));
+    // Opus players read R128 gains, relative to -23 LUFS in 1/256 dB steps
+    let r128 = |loudness: &Loudness| {
+        (((OPUS_REFERENCE - loudness.integrated) * 256.0).round() as i32).to_string()
+    };
+    let gain =
+        |loudness: &Loudness| format!("{:.2} dB", REPLAYGAIN_REFERENCE - loudness.integrated);
+    let peak = |loudness: &Loudness| format!("{:.6}", 10f64.powf(loudness.true_peak / 20.0));
+
+    let mut tags = Vec::new();
+    match is_opus {
+        true => tags.push(("R128_TRACK_GAIN", r128(track))),
+        false => {
+            tags.push(("REPLAYGAIN_TRACK_GAIN", gain(track)));
+            tags.push(("REPLAYGAIN_TRACK_PEAK", peak(track)));
+        }
+    }
+    if let Some(album) = album {
+        match is_opus {
+            true => tags.push(("R128_ALBUM_GAIN", r128(album))),
+            false => {
+                tags.push(("REPLAYGAIN_ALBUM_GAIN", gain(album)));
+                tags.push(("REPLAYGAIN_ALBUM_PEAK", peak(album)));
+            }
+        }
+    }
+    tags
+}
+
+// Measure the download and normalize or tag its tracks. Tracks split by
+// chapter are handled as an album: they are normalized with the same gain, and
+// get album gain tags from the full-length file. Returns the loudness of the
+// full-length file.
+pub fn apply(
+    audio: &Path,
+    tracks: &[&Path],
+    settings: &LoudnessSettings,
+) -> Result<Option<Loudness>, Box<dyn std::error::Error>> {
+    let Some(mode) = settings.mode else {
+        return Ok(None);
+    };
+    let Some(measured) = measure(audio, settings)? else {
+        return Ok(None);
+    };
+    let is_album = tracks != [audio];
+
+    for track in tracks {
+        match mode {
+            LoudnessMode::Normalize => normalize(track, &measured, settings)?,
+            LoudnessMode::ReplayGain if is_album => {
+                if let Some(track_measured) = measure(track, settings)? {
+                    let tags = gain_tags(track, &track_measured.loudness, Some(&measured.loudness));
+                    tags::write_custom_tags(track, &tags)?;
+                }
+            }
+            LoudnessMode::ReplayGain => {
+                tags::write_custom_tags(track, &gain_tags(track, &measured.loudness, None))?
+            }
+        }
+    }
+
+    Ok(Some(measured.loudness))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STDERR: &str = r#"Input #0, mp3, from 'song.mp3':
+  Duration: 00:03:20.00, start: 0.025057, bitrate: 160 kb/s
+  Stream #0:0: Audio: mp3 (mp3float), 44100 Hz, stereo, fltp, 160 kb/s
+[Parsed_loudnorm_0 @ 0x5581]
+{
+	"input_i" : "-9.12",
+	"input_tp" : "0.35",
+	"input_lra" : "5.10",
+	"input_thresh" : "-19.30",
+	"output_i" : "-14.02",
+	"output_tp" : "-1.00",
+	"output_lra" : "4.90",
+	"output_thresh" : "-24.20",
+	"normalization_type" : "dynamic",
+	"target_offset" : "0.02"
+}
+"#;
+
+    #[test]
+    fn loudnorm_output_is_parsed() {
+        let settings = LoudnessSettings::default();
+        let measured = parse_measurement(STDERR, &settings).unwrap().unwrap();
+        assert_eq!(measured.loudness.integrated, -9.12);
+        assert_eq!(measured.loudness.true_peak, 0.35);
+        assert_eq!(measured.loudness.threshold, -19.3);
+        assert_eq!(measured.loudness.gain, -4.88);
+        assert_eq!(measured.sample_rate.as_deref(), Some("44100"));
+        assert_eq!(measured.bitrate.as_deref(), Some("160"));
+
+        let silent = STDERR.replace("\"-9.12\"", "\"-inf\"");
+        assert!(parse_measurement(&silent, &settings).unwrap().is_none());
+    }
+
+    #[test]
+    fn replaygain_is_relative_to_its_reference() {
+        let loudness = Loudness {
+            integrated: -9.12,
+            true_peak: 0.0,
+            range: 5.1,
+            threshold: -19.3,
+            gain: 0.0,
+        };
+        let tags = gain_tags(Path::new("song.mp3"), &loudness, None);
+        assert_eq!(tags[0], ("REPLAYGAIN_TRACK_GAIN", "-8.88 dB".to_string()));
+        assert_eq!(tags[1], ("REPLAYGAIN_TRACK_PEAK", "1.000000".to_string()));
+
+        let tags = gain_tags(Path::new("song.opus"), &loudness, Some(&loudness));
+        assert_eq!(tags[0], ("R128_TRACK_GAIN", "-3553".to_string()));
+        assert_eq!(tags[1], ("R128_ALBUM_GAIN", "-3553".to_string()));
+    }
+}
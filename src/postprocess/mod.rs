@@ -0,0 +1,151 @@
+pub mod artwork;
+pub mod loudness;
+pub mod tags;
+
+use std::{
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::app_config::NetworkConfig;
+use crate::installer::ffmpeg_command;
+use crate::lyrics::{LyricsProvider, LyricsQuery};
+use crate::models::{music::Music, profile::DownloadProfile};
+
+// Steps of the profile that run on the files yt-dlp left behind. Returns the
+// video as it is recorded in the history. Optional steps that fail, like a
+// lyrics lookup, are added to `warnings` instead.
+pub fn run(
+    video: &Music,
+    profile: &DownloadProfile,
+    audio: Option<&Path>,
+    chapters: &[PathBuf],
+    network: &NetworkConfig,
+    warnings: &mut Vec<String>,
+) -> Result<Music, Box<dyn std::error::Error>> {
+    let split = profile.split_chapters && !chapters.is_empty();
+    let tracks: Vec<&Path> = match split {
+        true => chapters.iter().map(PathBuf::as_path).collect(),
+        false => audio.into_iter().collect(),
+    };
+    let mut record = video.clone();
+
+    // First, normalizing encodes the audio again and keeps the tags and artwork
+    if let Some(audio) = audio {
+        record.loudness = loudness::apply(audio, &tracks, &profile.loudness)?;
+    }
+
+    if split {
+        tags::tag_chapters(chapters, video, &profile.tags)?;
+    } else if let Some(audio) = audio.filter(|_| tags::is_tagging(video, &profile.tags)) {
+        tags::tag_track(audio, video, &profile.tags)?;
+    }
+
+    // Looked up with the tags written above
+    if let Some(config) = &profile.lyrics {
+        match config.provider(network) {
+            Ok(provider) => {
+                for track in &tracks {
+                    if let Err(e) = add_lyrics(track, provider.as_ref()) {
+                        warnings.push(network.redact(&format!("Lyrics lookup failed: {}", e)));
+                    }
+                }
+            }
+            Err(e) => warnings.push(network.redact(&format!("Lyrics lookup failed: {}", e))),
+        }
+    }
+
+    // The thumbnail is named after the full-length file
+    if let Some(audio) = audio.filter(|_| profile.artwork.is_enabled()) {
+        let thumbnail = audio.with_extension(profile.artwork.format().extension());
+        if thumbnail.exists() {
+            let artwork = artwork::process(&thumbnail, &profile.artwork)?;
+            for track in &tracks {
+                artwork::embed(track, &artwork)?;
+            }
+
+            // Album uploads split by chapter and playlists have their own folder
+            let folder = match split {
+                true => chapters[0].parent(),
+                false if video.playlist.is_some() && profile.playlist_folders => audio.parent(),
+                false => None,
+            };
+            if let Some(folder) = folder.filter(|_| profile.artwork.cover_file) {
+                artwork::write_cover(folder, &artwork)?;
+            }
+            fs::remove_file(&thumbnail)?;
+        }
+    }
+
+    // The tracks replace the full-length file
+    if let Some(audio) = audio.filter(|_| split) {
+        fs::remove_file(audio)?;
+    }
+
+    Ok(record)
+}
+
+// Save synced lyrics next to the track as an .lrc file and embed the unsynced
+// text
+fn add_lyrics(
+    track: &Path,
+    provider: &dyn LyricsProvider,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let tags = tags::read_tags(track)?;
+    let Some(title) = tags.title else {
+        return Ok(());
+    };
+    let query = LyricsQuery {
+        artist: tags.artist,
+        title,
+        album: tags.album,
+    };
+    let Some(lyrics) = provider.fetch(&query)? else {
+        return Ok(());
+    };
+
+    if let Some(synced) = &lyrics.synced {
+        fs::write(track.with_extension("lrc"), synced)?;
+    }
+    if let Some(text) = lyrics.plain_text() {
+        tags::write_lyrics(track, &text)?;
+    }
+    Ok(())
+}
+
+// Copy the file with ffmpeg, passing `args` between the input and the output,
+// and replace it with the copy
+fn ffmpeg_rewrite(file: &Path, args: &[OsString]) -> Result<(), Box<dyn std::error::Error>> {
+    // Same extension, ffmpeg picks the output format from it
+    let extension = file
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let temp = file.with_extension(format!("processing.{}", extension));
+
+    let output = Command::new(ffmpeg_command())
+        .args(["-v", "error", "-y", "-i"])
+        .arg(file)
+        .args(args)
+        .arg(&temp)
+        .output()?;
+
+    if !output.status.success() {
+        let _ = fs::remove_file(&temp);
+        return Err(ffmpeg_error(file, &output.stderr));
+    }
+
+    fs::rename(&temp, file)?;
+    Ok(())
+}
+
+fn ffmpeg_error(file: &Path, stderr: &[u8]) -> Box<dyn std::error::Error> {
+    format!(
+        "ffmpeg failed to process {}: {}",
+        file.display(),
+        String::from_utf8_lossy(stderr).trim()
+    )
+    .into()
+}
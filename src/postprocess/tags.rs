@@ -0,0 +1,430 @@
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use id3::{
+    frame::{ExtendedText, Lyrics, Timestamp},
+    TagLike, Version,
+};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use super::{ffmpeg_error, ffmpeg_rewrite};
+use crate::installer::ffmpeg_command;
+use crate::models::music::Music;
+
+// How the tags of a finished download are rewritten, part of a download profile
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TagSettings {
+    // Rewrite the tags yt-dlp copied from the video. Tracks split by chapter
+    // are always numbered.
+    pub enabled: bool,
+    // Tried in order against the video title until one matches, "{artist}"
+    // and "{title}" are the parts that are kept
+    pub title_patterns: Vec<String>,
+    // Album of videos that aren't part of a playlist
+    pub album: Option<String>,
+    pub genre: Option<String>,
+}
+
+impl Default for TagSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            title_patterns: vec![
+                "{artist} - {title}".to_string(),
+                "{artist} – {title}".to_string(),
+                "{artist} — {title}".to_string(),
+            ],
+            album: None,
+            genre: None,
+        }
+    }
+}
+
+// Tags of a single audio file, unset ones are left as they are
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TrackTags {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    // Track number and the number of tracks
+    pub track: Option<(u32, Option<u32>)>,
+    pub year: Option<i32>,
+    pub genre: Option<String>,
+}
+
+// Split a video title like "Artist - Title" into artist and title with the
+// first pattern that matches
+pub fn parse_title(title: &str, patterns: &[String]) -> Option<(Option<String>, String)> {
+    for pattern in patterns {
+        let Some(regex) = pattern_regex(pattern) else {
+            continue;
+        };
+        let Some(captures) = regex.captures(title) else {
+            continue;
+        };
+        let artist = captures
+            .name("artist")
+            .map(|artist| artist.as_str().trim().to_string());
+        let parsed = captures
+            .name("title")
+            .map_or(title, |title| title.as_str())
+            .trim()
+            .to_string();
+        if parsed.is_empty() || artist.as_deref() == Some("") {
+            continue;
+        }
+        return Some((artist, parsed));
+    }
+    None
+}
+
+// Turn "{artist} - {title}" into an anchored regex, the rest of the pattern is
+// matched literally
+fn pattern_regex(pattern: &str) -> Option<Regex> {
+    let mut regex = String::from("^");
+    let mut rest = pattern;
+
+    while let Some(start) = rest.find('{') {
+        regex.push_str(&regex::escape(&rest[..start]));
+        let end = start + rest[start..].find('}')?;
+        match &rest[start + 1..end] {
+            "artist" => regex.push_str("(?P<artist>.+?)"),
+            "title" => regex.push_str("(?P<title>.+)"),
+            _ => return None,
+        }
+        rest = &rest[end + 1..];
+    }
+    regex.push_str(&regex::escape(rest));
+    regex.push('$');
+
+    Regex::new(&regex).ok()
+}
+
+// Whether downloads are tagged beyond what yt-dlp embeds
+pub fn is_tagging(video: &Music, settings: &TagSettings) -> bool {
+    settings.enabled || video.metadata.is_some()
+}
+
+// Tags for a downloaded video: the details found by the metadata provider,
+// then what the title, the playlist and the tags yt-dlp embedded tell
+fn video_tags(video: &Music, settings: &TagSettings, existing: &TrackTags) -> TrackTags {
+    let info = video.metadata.clone().unwrap_or_default();
+    let title = video
+        .title
+        .clone()
+        .or_else(|| existing.title.clone())
+        .unwrap_or_default();
+    let (artist, title) = match parse_title(&title, &settings.title_patterns) {
+        Some((artist, title)) => (artist.or_else(|| existing.artist.clone()), title),
+        None => (existing.artist.clone(), title),
+    };
+
+    TrackTags {
+        title: info.title.or(Some(title).filter(|title| !title.is_empty())),
+        artist: info.artist.or(artist),
+        album: info
+            .album
+            .or_else(|| video.playlist.clone())
+            .or_else(|| settings.album.clone()),
+        track: info
+            .track
+            .or(video.playlist_index)
+            .map(|index| (index, None)),
+        year: info.year.or(existing.year),
+        genre: info.genre.or_else(|| settings.genre.clone()),
+    }
+}
+
+// Rewrite the tags of a downloaded audio file
+pub fn tag_track(
+    file: &Path,
+    video: &Music,
+    settings: &TagSettings,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let existing = read_tags(file)?;
+    write_tags(file, &video_tags(video, settings, &existing))
+}
+
+// Tag tracks split by chapter with their number and chapter title. The album
+// is the video, yt-dlp copies its tags into every track.
+pub fn tag_chapters(
+    tracks: &[PathBuf],
+    video: &Music,
+    settings: &TagSettings,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let total = tracks.len() as u32;
+
+    for (idx, track) in tracks.iter().enumerate() {
+        let stem = track
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        // Named "01 - Chapter title" by the chapter output template
+        let chapter = stem
+            .split_once(" - ")
+            .map_or(stem.as_str(), |(_, title)| title);
+        let folder = track
+            .parent()
+            .and_then(Path::file_name)
+            .map(|name| name.to_string_lossy().to_string());
+
+        let tags = match is_tagging(video, settings) {
+            true => {
+                let existing = read_tags(track)?;
+                let album = video_tags(video, settings, &existing);
+                // The video's own title names the album, unless the provider
+                // knows it
+                let album_title = video
+                    .metadata
+                    .as_ref()
+                    .and_then(|info| info.album.clone())
+                    .or(album.title);
+                TrackTags {
+                    title: Some(chapter.to_string()),
+                    album: album_title.or(folder),
+                    track: Some((idx as u32 + 1, Some(total))),
+                    ..album
+                }
+            }
+            false => TrackTags {
+                title: Some(chapter.to_string()),
+                album: folder,
+                track: Some((idx as u32 + 1, Some(total))),
+                ..TrackTags::default()
+            },
+        };
+        write_tags(track, &tags)?;
+    }
+
+    Ok(())
+}
+
+fn is_mp3(file: &Path) -> bool {
+    file.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("mp3"))
+}
+
+fn is_mp4(file: &Path) -> bool {
+    file.extension().is_some_and(|ext| {
+        ["m4a", "mp4", "m4b"]
+            .iter()
+            .any(|mp4| ext.eq_ignore_ascii_case(mp4))
+    })
+}
+
+// ID3 tag of an MP3 file, empty if it has none yet
+pub fn id3_tag(file: &Path) -> Result<id3::Tag, Box<dyn std::error::Error>> {
+    match id3::Tag::read_from_path(file) {
+        Ok(tag) => Ok(tag),
+        Err(id3::Error {
+            kind: id3::ErrorKind::NoTag,
+            ..
+        }) => Ok(id3::Tag::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+// First four digits of a date like "20240131" or "2024-01-31"
+fn parse_year(date: &str) -> Option<i32> {
+    date.trim().get(..4)?.parse().ok()
+}
+
+// Read the tags yt-dlp embedded, ID3 natively and other formats through ffmpeg
+pub fn read_tags(file: &Path) -> Result<TrackTags, Box<dyn std::error::Error>> {
+    if is_mp3(file) {
+        let tag = id3_tag(file)?;
+        return Ok(TrackTags {
+            title: tag.title().map(str::to_string),
+            artist: tag.artist().map(str::to_string),
+            album: tag.album().map(str::to_string),
+            year: ["TDRC", "TYER"]
+                .iter()
+                .find_map(|id| tag.get(id)?.content().text().and_then(parse_year)),
+            ..TrackTags::default()
+        });
+    }
+
+    let output = Command::new(ffmpeg_command())
+        .args(["-v", "error", "-i"])
+        .arg(file)
+        .args(["-f", "ffmetadata", "-"])
+        .output()?;
+    if !output.status.success() {
+        return Err(ffmpeg_error(file, &output.stderr));
+    }
+
+    let mut tags = TrackTags::default();
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let value = value.to_string();
+        match key.to_lowercase().as_str() {
+            "title" => tags.title = Some(value),
+            "artist" => tags.artist = Some(value),
+            "album" => tags.album = Some(value),
+            "date" => tags.year = parse_year(&value),
+            _ => {}
+        }
+    }
+    Ok(tags)
+}
+
+// Write ID3v2.4 tags natively, and Vorbis comments or MP4 atoms through
+// ffmpeg, which picks the right ones for the container
+pub fn write_tags(file: &Path, tags: &TrackTags) -> Result<(), Box<dyn std::error::Error>> {
+    if is_mp3(file) {
+        let mut tag = id3_tag(file)?;
+        if let Some(title) = &tags.title {
+            tag.set_title(title);
+        }
+        if let Some(artist) = &tags.artist {
+            tag.set_artist(artist);
+        }
+        if let Some(album) = &tags.album {
+            tag.set_album(album);
+        }
+        if let Some((track, total)) = tags.track {
+            tag.set_track(track);
+            match total {
+                Some(total) => tag.set_total_tracks(total),
+                None => tag.remove_total_tracks(),
+            }
+        }
+        if let Some(year) = tags.year {
+            tag.set_date_recorded(Timestamp {
+                year,
+                month: None,
+                day: None,
+                hour: None,
+                minute: None,
+                second: None,
+            });
+        }
+        if let Some(genre) = &tags.genre {
+            tag.set_genre(genre);
+        }
+        tag.write_to_path(file, Version::Id3v24)?;
+        return Ok(());
+    }
+
+    let track = tags.track.map(|(track, total)| match total {
+        Some(total) => format!("{}/{}", track, total),
+        None => track.to_string(),
+    });
+    let year = tags.year.map(|year| year.to_string());
+    let values = [
+        ("title", tags.title.as_deref()),
+        ("artist", tags.artist.as_deref()),
+        ("album", tags.album.as_deref()),
+        ("track", track.as_deref()),
+        ("date", year.as_deref()),
+        ("genre", tags.genre.as_deref()),
+    ];
+    let values: Vec<(&str, &str)> = values
+        .into_iter()
+        .filter_map(|(name, value)| Some((name, value?)))
+        .collect();
+    rewrite_tags(file, &values)
+}
+
+// Embed unsynced lyrics, as an ID3 USLT frame or a "lyrics" tag which ffmpeg
+// writes as a Vorbis comment or an MP4 lyrics atom
+pub fn write_lyrics(file: &Path, text: &str) -> Result<(), Box<dyn std::error::Error>> {
+    if is_mp3(file) {
+        let mut tag = id3_tag(file)?;
+        tag.remove_all_lyrics();
+        tag.add_frame(Lyrics {
+            // Unknown language
+            lang: "XXX".to_string(),
+            description: String::new(),
+            text: text.to_string(),
+        });
+        tag.write_to_path(file, Version::Id3v24)?;
+        return Ok(());
+    }
+
+    rewrite_tags(file, &[("lyrics", text)])
+}
+
+// Write tags without a standard field, as ID3 user text frames or Vorbis
+// comments. MP4 files can't hold them through ffmpeg and are left as they are.
+pub fn write_custom_tags(
+    file: &Path,
+    tags: &[(&str, String)],
+) -> Result<(), Box<dyn std::error::Error>> {
+    if is_mp3(file) {
+        let mut tag = id3_tag(file)?;
+        for (name, value) in tags {
+            tag.remove_extended_text(Some(name), None);
+            tag.add_frame(ExtendedText {
+                description: name.to_string(),
+                value: value.clone(),
+            });
+        }
+        tag.write_to_path(file, Version::Id3v24)?;
+        return Ok(());
+    }
+    // ffmpeg drops unknown keys from MP4 files, rewriting them would gain nothing
+    if is_mp4(file) {
+        return Ok(());
+    }
+
+    let tags: Vec<(&str, &str)> = tags
+        .iter()
+        .map(|(name, value)| (*name, value.as_str()))
+        .collect();
+    rewrite_tags(file, &tags)
+}
+
+// Copy the file with ffmpeg, changing only the given tags, and replace it
+fn rewrite_tags(file: &Path, tags: &[(&str, &str)]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut args = vec!["-map".into(), "0".into(), "-c".into(), "copy".into()];
+    for (name, value) in tags {
+        args.push("-metadata".into());
+        args.push(format!("{}={}", name, value).into());
+    }
+    ffmpeg_rewrite(file, &args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn titles_are_split_with_the_first_matching_pattern() {
+        let patterns = TagSettings::default().title_patterns;
+        assert_eq!(
+            parse_title("Daft Punk - Get Lucky (Official Audio)", &patterns),
+            Some((
+                Some("Daft Punk".to_string()),
+                "Get Lucky (Official Audio)".to_string()
+            ))
+        );
+        assert_eq!(
+            parse_title("Artist – Title", &patterns),
+            Some((Some("Artist".to_string()), "Title".to_string()))
+        );
+        assert_eq!(parse_title("Just a title", &patterns), None);
+
+        let custom = vec!["{title} by {artist}".to_string(), "{title} [*]".to_string()];
+        assert_eq!(
+            parse_title("Song by Someone", &custom),
+            Some((Some("Someone".to_string()), "Song".to_string()))
+        );
+        assert_eq!(
+            parse_title("Song [*]", &custom),
+            Some((None, "Song".to_string()))
+        );
+    }
+
+    #[test]
+    fn unknown_placeholders_are_ignored() {
+        assert!(pattern_regex("{artist} - {album}").is_none());
+        assert_eq!(parse_title("A - B", &["{album}".to_string()]), None);
+    }
+}
@@ -6,23 +6,49 @@ use std::{
     },
 };
 
-use crate::models::music::Music;
+use crate::app_config::ConfigCache;
+
+// Finished, failed and cancelled downloads kept for status requests, older
+// ones are dropped so a long-running daemon doesn't grow without bound
+pub const KEEP_FINISHED_DOWNLOADS: usize = 100;
+use crate::models::{
+    download::{Download, DownloadState, Interruption},
+    history::History,
+    limits::{LimitOverrides, Limits},
+    music::Music,
+};
 
 pub trait RuntimeTrait {
-    fn get_url_buffer(&self) -> MutexGuard<VecDeque<Music>>;
+    fn get_url_buffer(&self) -> MutexGuard<'_, VecDeque<Music>>;
     fn drain_buffer(&self) -> Vec<Music>;
     fn clear_url_buffer(&self);
+    fn get_downloads(&self) -> MutexGuard<'_, Vec<Download>>;
+    fn add_download(&self, download: Download);
+    fn set_download_state(&self, url: &str, state: DownloadState);
+    fn set_download_warnings(&self, url: &str, warnings: Vec<String>);
+    fn interrupt_download(&self, url: &str, interruption: Interruption) -> bool;
+    fn interrupt_downloads(&self, interruption: Interruption) -> usize;
+    fn get_history(&self) -> MutexGuard<'_, History>;
+    fn record_download(&self, video: &Music);
+    fn flush_history(&self);
+    fn get_limits(&self) -> Limits;
     fn start(&self) -> bool;
     fn stop(&self);
 }
 
+#[derive(Clone)]
 pub struct Runtime {
     pub url_buffer: Arc<Mutex<VecDeque<Music>>>,
+    pub downloads: Arc<Mutex<Vec<Download>>>,
+    // Loaded once and shared, so concurrent workers never overwrite each other's entries
+    pub history: Arc<Mutex<History>>,
     pub state: Arc<AtomicBool>,
+    pub limit_overrides: LimitOverrides,
+    pub config: ConfigCache,
 }
 
 impl RuntimeTrait for Runtime {
-    fn get_url_buffer(&self) -> MutexGuard<VecDeque<Music>> {
+    fn get_url_buffer(&self) -> MutexGuard<'_, VecDeque<Music>> {
         self.url_buffer.lock().unwrap()
     }
 
@@ -35,6 +61,87 @@ impl RuntimeTrait for Runtime {
         buffer.clear();
     }
 
+    fn get_downloads(&self) -> MutexGuard<'_, Vec<Download>> {
+        self.downloads.lock().unwrap()
+    }
+
+    fn add_download(&self, download: Download) {
+        let mut downloads = self.downloads.lock().unwrap();
+        downloads.push(download);
+
+        let finished = downloads.iter().filter(|d| !d.is_active()).count();
+        let mut excess = finished.saturating_sub(KEEP_FINISHED_DOWNLOADS);
+        downloads.retain(|d| {
+            let remove = excess > 0 && !d.is_active();
+            if remove {
+                excess -= 1;
+            }
+            !remove
+        });
+    }
+
+    fn set_download_state(&self, url: &str, state: DownloadState) {
+        let mut downloads = self.downloads.lock().unwrap();
+        // The same URL may be downloaded again later, the newest entry is the live one
+        if let Some(download) = downloads.iter_mut().rev().find(|d| d.music.url == url) {
+            download.state = state;
+        }
+    }
+
+    fn set_download_warnings(&self, url: &str, warnings: Vec<String>) {
+        let mut downloads = self.downloads.lock().unwrap();
+        if let Some(download) = downloads.iter_mut().rev().find(|d| d.music.url == url) {
+            download.warnings = warnings;
+        }
+    }
+
+    fn interrupt_download(&self, url: &str, interruption: Interruption) -> bool {
+        let downloads = self.downloads.lock().unwrap();
+        match downloads
+            .iter()
+            .rev()
+            .find(|d| d.music.url == url && d.is_active())
+        {
+            Some(download) => {
+                download.token.interrupt(interruption);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Interrupt every waiting or running download, returns how many there were
+    fn interrupt_downloads(&self, interruption: Interruption) -> usize {
+        let downloads = self.downloads.lock().unwrap();
+        downloads
+            .iter()
+            .filter(|d| d.is_active())
+            .inspect(|d| d.token.interrupt(interruption))
+            .count()
+    }
+
+    fn get_history(&self) -> MutexGuard<'_, History> {
+        self.history.lock().unwrap()
+    }
+
+    // Shown right away, written to the file by `flush_history`
+    fn record_download(&self, video: &Music) {
+        self.history.lock().unwrap().add(video);
+    }
+
+    fn flush_history(&self) {
+        self.history.lock().unwrap().flush();
+    }
+
+    // Taken from the current config, so changes in the settings apply to
+    // downloads that haven't started yet
+    fn get_limits(&self) -> Limits {
+        self.config
+            .get()
+            .limits
+            .with_overrides(&self.limit_overrides)
+    }
+
     fn start(&self) -> bool {
         self.state.load(Ordering::SeqCst)
     }
@@ -43,3 +150,46 @@ impl RuntimeTrait for Runtime {
         self.state.store(false, Ordering::SeqCst);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn music(url: &str) -> Music {
+        Music {
+            url: url.to_string(),
+            title: None,
+            downloaded_at: None,
+            playlist: None,
+            playlist_index: None,
+            loudness: None,
+            metadata: None,
+            subtitles: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn only_the_newest_finished_downloads_are_kept() {
+        let runtime = Runtime {
+            url_buffer: Arc::default(),
+            downloads: Arc::default(),
+            history: Arc::default(),
+            state: Arc::new(AtomicBool::new(true)),
+            limit_overrides: LimitOverrides::default(),
+            config: ConfigCache::default(),
+        };
+        runtime.add_download(Download::new(music("running")));
+        for i in 0..KEEP_FINISHED_DOWNLOADS + 5 {
+            let url = format!("finished {}", i);
+            runtime.add_download(Download::new(music(&url)));
+            runtime.set_download_state(&url, DownloadState::Finished(1));
+        }
+        runtime.add_download(Download::new(music("new")));
+
+        let downloads = runtime.get_downloads();
+        assert_eq!(downloads.len(), KEEP_FINISHED_DOWNLOADS + 2);
+        assert_eq!(downloads[0].music.url, "running");
+        assert_eq!(downloads[1].music.url, "finished 5");
+        assert_eq!(downloads.last().unwrap().music.url, "new");
+    }
+}
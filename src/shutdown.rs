@@ -0,0 +1,82 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::{
+    models::{download::Interruption, queue::SavedQueue, translation::Translations},
+    runtime::{Runtime, RuntimeTrait},
+};
+
+// Handle Ctrl-C and termination signals. The first one stops the runtime and
+// pauses the running downloads, so workers wind down and their items go back
+// to the queue. A second one quits right away.
+//
+// `exit_when_idle` is for the numbered menu, which is blocked reading input
+// and would not notice the runtime stopping until the next line is entered.
+pub fn install_handler(
+    runtime: Runtime,
+    exit_when_idle: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let stopping = AtomicBool::new(false);
+
+    ctrlc::set_handler(move || {
+        if stopping.swap(true, Ordering::SeqCst) {
+            println!("\n{}", Translations::t("shutdown_forced", None));
+            save_queue(&runtime);
+            runtime.flush_history();
+            std::process::exit(130);
+        }
+
+        runtime.stop();
+        let running = runtime.interrupt_downloads(Interruption::Paused);
+        if running == 0 && exit_when_idle {
+            println!();
+            save_queue(&runtime);
+            println!("{}", Translations::t("app_stopped", None));
+            std::process::exit(130);
+        }
+        println!("\n{}", Translations::t("shutdown_started", None));
+    })?;
+
+    Ok(())
+}
+
+// Put the queue back from the last run
+pub fn restore_queue(runtime: &Runtime) {
+    let saved = SavedQueue::load().videos;
+    if saved.is_empty() {
+        return;
+    }
+
+    println!(
+        "{}",
+        Translations::t("queue_restored", Some(&[("count", saved.len().into())]))
+    );
+    let mut buffer = runtime.get_url_buffer();
+    for video in saved {
+        if !buffer.contains(&video) {
+            buffer.push_back(video);
+        }
+    }
+}
+
+// Save the queue together with anything still downloading, which would
+// otherwise be lost when quitting before the downloads are paused
+pub fn save_queue(runtime: &Runtime) {
+    let mut videos: Vec<_> = runtime
+        .get_downloads()
+        .iter()
+        .filter(|d| d.is_active())
+        .map(|d| d.music.clone())
+        .collect();
+    for video in runtime.get_url_buffer().iter() {
+        if !videos.contains(video) {
+            videos.push(video.clone());
+        }
+    }
+
+    let queue = SavedQueue {
+        videos: videos.into(),
+    };
+    if let Err(e) = queue.save() {
+        println!("Failed to save the download queue: {}", e);
+    }
+}
@@ -0,0 +1,46 @@
+// Local HTTP servers standing in for the web services in tests
+
+use std::sync::mpsc;
+
+use tiny_http::{Header, Response, Server};
+
+fn bind() -> (Server, String) {
+    let server = Server::http("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", server.server_addr().to_ip().unwrap());
+    (server, url)
+}
+
+// Answer a single request with a JSON `body` and send back the requested URL
+pub fn respond_once(status: u16, body: &'static str) -> (String, mpsc::Receiver<String>) {
+    let (server, url) = bind();
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        let request = server.recv().unwrap();
+        sender.send(request.url().to_string()).unwrap();
+        let header = Header::from_bytes("Content-Type", "application/json").unwrap();
+        request
+            .respond(
+                Response::from_string(body)
+                    .with_status_code(status)
+                    .with_header(header),
+            )
+            .unwrap();
+    });
+    (url, receiver)
+}
+
+// Serve `routes` (path and body) until the test process exits, 404 for other
+// paths
+pub fn serve_routes(routes: Vec<(String, Vec<u8>)>) -> String {
+    let (server, url) = bind();
+    std::thread::spawn(move || {
+        for request in server.incoming_requests() {
+            let response = match routes.iter().find(|(path, _)| path == request.url()) {
+                Some((_, body)) => Response::from_data(body.clone()),
+                None => Response::from_data(Vec::new()).with_status_code(404),
+            };
+            request.respond(response).unwrap();
+        }
+    });
+    url
+}
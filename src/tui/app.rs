@@ -0,0 +1,508 @@
+use std::{
+    sync::mpsc::{self, Receiver, Sender},
+    thread::{self, JoinHandle},
+};
+
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+use crate::{
+    app_config::Config,
+    downloader::{download_batch, fetch_playlist_videos},
+    models::{
+        download::Interruption,
+        history::History,
+        language::Language,
+        limits::{DownloadWindow, Limits, Rate},
+        music::Music,
+        translation::Translations,
+    },
+    runtime::{Runtime, RuntimeTrait},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Pane {
+    Queue,
+    Downloads,
+    History,
+    Settings,
+}
+
+impl Pane {
+    pub fn all() -> Vec<Pane> {
+        vec![Pane::Queue, Pane::Downloads, Pane::History, Pane::Settings]
+    }
+
+    pub fn title_key(self) -> &'static str {
+        match self {
+            Pane::Queue => "tui_queue",
+            Pane::Downloads => "tui_downloads",
+            Pane::History => "tui_history",
+            Pane::Settings => "tui_settings",
+        }
+    }
+
+    pub fn help_key(self) -> &'static str {
+        match self {
+            Pane::Queue => "tui_help_queue",
+            Pane::Downloads => "tui_help_downloads",
+            Pane::History => "tui_help_history",
+            Pane::Settings => "tui_help_settings",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SettingsField {
+    Language,
+    Directory,
+    Coloring,
+    MaxRate,
+    Window,
+}
+
+impl SettingsField {
+    pub fn all() -> Vec<SettingsField> {
+        vec![
+            SettingsField::Language,
+            SettingsField::Directory,
+            SettingsField::Coloring,
+            SettingsField::MaxRate,
+            SettingsField::Window,
+        ]
+    }
+}
+
+// What the text typed into the input line is used for
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InputPurpose {
+    AddUrl,
+    Search,
+    Directory,
+    MaxRate,
+    Window,
+}
+
+#[derive(Debug)]
+pub struct Input {
+    pub purpose: InputPurpose,
+    pub value: String,
+}
+
+pub struct App {
+    pub runtime: Runtime,
+    pub config: Config,
+    pub pane: Pane,
+    pub queue_selected: usize,
+    pub downloads_selected: usize,
+    pub history: History,
+    pub history_search: String,
+    pub history_selected: usize,
+    pub settings_selected: usize,
+    pub input: Option<Input>,
+    pub status: String,
+    worker: Option<JoinHandle<()>>,
+    // Links looked up on background threads, with the videos found or the error
+    fetch_sender: Sender<Result<Vec<Music>, String>>,
+    fetched: Receiver<Result<Vec<Music>, String>>,
+}
+
+impl App {
+    pub fn new(runtime: Runtime) -> Self {
+        let (fetch_sender, fetched) = mpsc::channel();
+        let history = runtime.get_history().clone();
+        Self {
+            runtime,
+            config: Config::load(),
+            pane: Pane::Queue,
+            queue_selected: 0,
+            downloads_selected: 0,
+            history,
+            history_search: String::new(),
+            history_selected: 0,
+            settings_selected: 0,
+            input: None,
+            status: String::new(),
+            worker: None,
+            fetch_sender,
+            fetched,
+        }
+    }
+
+    pub fn is_downloading(&self) -> bool {
+        self.worker
+            .as_ref()
+            .is_some_and(|worker| !worker.is_finished())
+    }
+
+    // Pick up new history entries, fetched links and the results of a
+    // finished download batch
+    pub fn tick(&mut self) {
+        while let Ok(result) = self.fetched.try_recv() {
+            self.queue_fetched(result);
+        }
+
+        let history = self.runtime.get_history();
+        if history.downloads.len() != self.history.downloads.len() {
+            self.history = history.clone();
+        }
+        drop(history);
+
+        if self
+            .worker
+            .as_ref()
+            .is_some_and(|worker| worker.is_finished())
+        {
+            if let Some(worker) = self.worker.take() {
+                let _ = worker.join();
+            }
+            self.status = Translations::t("tui_downloads_finished", None);
+        }
+    }
+
+    // History entries matching the search, newest first
+    pub fn filtered_history(&self) -> Vec<&Music> {
+        let search = self.history_search.to_lowercase();
+        self.history
+            .downloads
+            .iter()
+            .rev()
+            .filter(|music| {
+                search.is_empty()
+                    || music
+                        .title
+                        .as_deref()
+                        .unwrap_or_default()
+                        .to_lowercase()
+                        .contains(&search)
+            })
+            .collect()
+    }
+
+    // Handle a key press; returns false when the application should quit
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        if self.input.is_some() {
+            self.handle_input_key(key);
+            return true;
+        }
+
+        match key.code {
+            KeyCode::Char('q') => {
+                if self.is_downloading() {
+                    self.status = Translations::t("tui_downloads_running", None);
+                } else {
+                    return false;
+                }
+            }
+            // Like in the numbered menu, Ctrl-C first stops a running batch
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                if self.is_downloading() {
+                    self.stop_downloads();
+                } else {
+                    return false;
+                }
+            }
+            KeyCode::Tab => self.switch_pane(1),
+            KeyCode::BackTab => self.switch_pane(Pane::all().len() - 1),
+            KeyCode::F(n) if (1..=4).contains(&n) => self.pane = Pane::all()[n as usize - 1],
+            _ => match self.pane {
+                Pane::Queue => self.handle_queue_key(key),
+                Pane::Downloads => self.handle_downloads_key(key),
+                Pane::History => self.handle_history_key(key),
+                Pane::Settings => self.handle_settings_key(key),
+            },
+        }
+
+        true
+    }
+
+    fn switch_pane(&mut self, offset: usize) {
+        let panes = Pane::all();
+        let current = panes.iter().position(|p| *p == self.pane).unwrap_or(0);
+        self.pane = panes[(current + offset) % panes.len()];
+    }
+
+    fn handle_queue_key(&mut self, key: KeyEvent) {
+        let len = self.runtime.get_url_buffer().len();
+
+        match key.code {
+            // Shift+arrow (or K/J) moves the selected item
+            KeyCode::Up if key.modifiers.contains(KeyModifiers::SHIFT) => self.move_selected(-1),
+            KeyCode::Down if key.modifiers.contains(KeyModifiers::SHIFT) => self.move_selected(1),
+            KeyCode::Char('K') => self.move_selected(-1),
+            KeyCode::Char('J') => self.move_selected(1),
+            KeyCode::Up => self.queue_selected = self.queue_selected.saturating_sub(1),
+            KeyCode::Down if self.queue_selected + 1 < len => self.queue_selected += 1,
+            KeyCode::Char('a') => {
+                self.input = Some(Input {
+                    purpose: InputPurpose::AddUrl,
+                    value: String::new(),
+                })
+            }
+            KeyCode::Char('x') | KeyCode::Delete => {
+                let mut buffer = self.runtime.get_url_buffer();
+                if self.queue_selected < buffer.len() {
+                    buffer.remove(self.queue_selected);
+                }
+                self.queue_selected = self.queue_selected.min(buffer.len().saturating_sub(1));
+            }
+            KeyCode::Char('c') => {
+                let count = self.runtime.get_url_buffer().len();
+                self.runtime.clear_url_buffer();
+                self.queue_selected = 0;
+                self.status = Translations::t("queue_cleared", Some(&[("count", count.into())]));
+            }
+            KeyCode::Char('d') => self.start_downloads(),
+            _ => {}
+        }
+    }
+
+    fn move_selected(&mut self, offset: isize) {
+        let mut buffer = self.runtime.get_url_buffer();
+        let target = self.queue_selected as isize + offset;
+        if target < 0 || target as usize >= buffer.len() {
+            return;
+        }
+        buffer.swap(self.queue_selected, target as usize);
+        self.queue_selected = target as usize;
+    }
+
+    fn handle_downloads_key(&mut self, key: KeyEvent) {
+        let len = self.runtime.get_downloads().len();
+
+        match key.code {
+            KeyCode::Up => self.downloads_selected = self.downloads_selected.saturating_sub(1),
+            KeyCode::Down if self.downloads_selected + 1 < len => self.downloads_selected += 1,
+            KeyCode::Char('p') => self.interrupt_selected(Interruption::Paused),
+            KeyCode::Char('x') | KeyCode::Delete => {
+                self.interrupt_selected(Interruption::Cancelled)
+            }
+            KeyCode::Char('s') => self.stop_downloads(),
+            _ => {}
+        }
+    }
+
+    fn interrupt_selected(&mut self, interruption: Interruption) {
+        let url = self
+            .runtime
+            .get_downloads()
+            .get(self.downloads_selected)
+            .map(|d| d.music.url.clone());
+        let interrupted =
+            url.is_some_and(|url| self.runtime.interrupt_download(&url, interruption));
+        if !interrupted {
+            self.status = Translations::t("tui_download_not_active", None);
+        }
+    }
+
+    // Pause the whole batch, unfinished items end up back in the queue
+    fn stop_downloads(&mut self) {
+        if self.runtime.interrupt_downloads(Interruption::Paused) > 0 {
+            self.status = Translations::t("downloads_pausing", None);
+        }
+    }
+
+    fn handle_history_key(&mut self, key: KeyEvent) {
+        let len = self.filtered_history().len();
+
+        match key.code {
+            KeyCode::Up => self.history_selected = self.history_selected.saturating_sub(1),
+            KeyCode::Down if self.history_selected + 1 < len => self.history_selected += 1,
+            KeyCode::Char('/') => {
+                self.input = Some(Input {
+                    purpose: InputPurpose::Search,
+                    value: self.history_search.clone(),
+                })
+            }
+            // Picks up downloads recorded by other instances, e.g. the daemon
+            KeyCode::Char('r') => {
+                self.runtime.get_history().reload();
+                self.history = self.runtime.get_history().clone();
+            }
+            KeyCode::Esc => {
+                self.history_search.clear();
+                self.history_selected = 0;
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_settings_key(&mut self, key: KeyEvent) {
+        let fields = SettingsField::all();
+
+        match key.code {
+            KeyCode::Up => self.settings_selected = self.settings_selected.saturating_sub(1),
+            KeyCode::Down if self.settings_selected + 1 < fields.len() => {
+                self.settings_selected += 1
+            }
+            KeyCode::Left | KeyCode::Right | KeyCode::Enter | KeyCode::Char(' ') => {
+                match fields[self.settings_selected] {
+                    SettingsField::Language => {
+                        let languages = Language::all();
+                        let current = languages
+                            .iter()
+                            .position(|l| *l == self.config.language)
+                            .unwrap_or(0);
+                        let next = match key.code {
+                            KeyCode::Left => current + languages.len() - 1,
+                            _ => current + 1,
+                        } % languages.len();
+                        let language = languages[next];
+
+                        self.save_setting(|config| config.set_language(language));
+                        Translations::change_language(language);
+                    }
+                    SettingsField::Directory => {
+                        self.input = Some(Input {
+                            purpose: InputPurpose::Directory,
+                            value: self.config.download_dir.clone(),
+                        })
+                    }
+                    SettingsField::MaxRate => {
+                        self.input = Some(Input {
+                            purpose: InputPurpose::MaxRate,
+                            value: self
+                                .config
+                                .limits
+                                .max_rate
+                                .map(|rate| rate.to_string())
+                                .unwrap_or_default(),
+                        })
+                    }
+                    SettingsField::Window => {
+                        self.input = Some(Input {
+                            purpose: InputPurpose::Window,
+                            value: self
+                                .config
+                                .limits
+                                .window
+                                .map(|window| window.to_string())
+                                .unwrap_or_default(),
+                        })
+                    }
+                    SettingsField::Coloring => {
+                        // Only saved, the TUI itself never prints ANSI colors
+                        let coloring = !self.config.coloring;
+                        self.save_setting(|config| config.set_coloring(coloring));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn save_setting(
+        &mut self,
+        update: impl FnOnce(&mut Config) -> Result<(), Box<dyn std::error::Error>>,
+    ) {
+        if let Err(e) = update(&mut self.config) {
+            self.status = e.to_string();
+        }
+    }
+
+    fn set_limits(&mut self, update: impl FnOnce(&mut Limits)) {
+        let mut limits = self.config.limits;
+        update(&mut limits);
+        self.save_setting(|config| config.set_limits(limits));
+    }
+
+    fn handle_input_key(&mut self, key: KeyEvent) {
+        let Some(input) = self.input.as_mut() else {
+            return;
+        };
+
+        match key.code {
+            KeyCode::Esc => self.input = None,
+            KeyCode::Backspace => {
+                input.value.pop();
+            }
+            KeyCode::Char(c) => input.value.push(c),
+            KeyCode::Enter => {
+                let Input { purpose, value } = self.input.take().unwrap();
+                let value = value.trim().to_string();
+                match purpose {
+                    InputPurpose::AddUrl if !value.is_empty() => self.add_url(&value),
+                    InputPurpose::AddUrl => {}
+                    InputPurpose::Search => {
+                        self.history_search = value;
+                        self.history_selected = 0;
+                    }
+                    InputPurpose::Directory if !value.is_empty() => {
+                        self.save_setting(|config| config.set_download_dir(value.clone()));
+                        self.status = Translations::t("dir_set", Some(&[("dir", value.into())]));
+                    }
+                    InputPurpose::Directory => {}
+                    // Left empty removes the limit
+                    InputPurpose::MaxRate => match value.parse::<Rate>() {
+                        Ok(rate) => self.set_limits(|limits| limits.max_rate = Some(rate)),
+                        Err(_) if value.is_empty() => {
+                            self.set_limits(|limits| limits.max_rate = None)
+                        }
+                        Err(_) => self.status = Translations::t("invalid_max_rate", None),
+                    },
+                    InputPurpose::Window => match value.parse::<DownloadWindow>() {
+                        Ok(window) => self.set_limits(|limits| limits.window = Some(window)),
+                        Err(_) if value.is_empty() => {
+                            self.set_limits(|limits| limits.window = None)
+                        }
+                        Err(_) => self.status = Translations::t("invalid_window", None),
+                    },
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Look the link up on a background thread, `tick` adds the videos to the
+    // queue once they arrive
+    fn add_url(&mut self, url: &str) {
+        self.status = Translations::t("tui_fetching", Some(&[("url", url.into())]));
+        let sender = self.fetch_sender.clone();
+        let url = url.to_string();
+        thread::spawn(move || {
+            let result = fetch_playlist_videos(&url).map_err(|e| e.to_string());
+            let _ = sender.send(result);
+        });
+    }
+
+    fn queue_fetched(&mut self, result: Result<Vec<Music>, String>) {
+        match result {
+            Ok(videos) => {
+                let mut buffer = self.runtime.get_url_buffer();
+                let mut added = 0usize;
+                for video in videos {
+                    if !buffer.contains(&video) {
+                        buffer.push_back(video);
+                        added += 1;
+                    }
+                }
+                self.status = Translations::t("tui_added", Some(&[("count", added.into())]));
+            }
+            Err(e) => self.status = Translations::t("error_fetching", Some(&[("error", e.into())])),
+        }
+    }
+
+    // Download the queue on a background thread so the interface stays responsive
+    fn start_downloads(&mut self) {
+        if self.is_downloading() {
+            self.status = Translations::t("tui_downloads_running", None);
+            return;
+        }
+
+        let videos = self.runtime.drain_buffer();
+        if videos.is_empty() {
+            self.status = Translations::t("no_urls_to_download", None);
+            return;
+        }
+
+        self.status = Translations::t("starting_download", Some(&[("count", videos.len().into())]));
+        self.queue_selected = 0;
+        self.downloads_selected = 0;
+        self.pane = Pane::Downloads;
+
+        let runtime = self.runtime.clone();
+        let dir = self.config.get_download_dir().to_string();
+        self.worker = Some(thread::spawn(move || {
+            download_batch(&runtime, videos, &dir, |_, _| {});
+        }));
+    }
+}
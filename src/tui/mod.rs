@@ -0,0 +1,52 @@
+use std::time::Duration;
+
+use ratatui::crossterm::event::{self, Event, KeyEventKind};
+
+use crate::{
+    models::translation::Translations,
+    runtime::{Runtime, RuntimeTrait},
+    tui::app::App,
+};
+
+pub mod app;
+pub mod ui;
+
+// Run the full-screen interface until the user quits. It works on the same
+// runtime as the numbered menu, so the queue is shared between the two.
+pub fn run(runtime: Runtime) -> Result<(), Box<dyn std::error::Error>> {
+    // ANSI color tags would garble the screen, the TUI has its own styling
+    Translations::set_coloring(false);
+
+    let mut terminal = ratatui::init();
+    let mut app = App::new(runtime);
+
+    let result = (|| -> Result<(), Box<dyn std::error::Error>> {
+        loop {
+            app.tick();
+
+            // Stopped by a signal, quit once the running downloads are paused
+            if !app.runtime.start() && !app.is_downloading() {
+                return Ok(());
+            }
+            terminal.draw(|frame| ui::draw(frame, &app))?;
+
+            // Redraw regularly so download progress keeps moving
+            if !event::poll(Duration::from_millis(200))? {
+                continue;
+            }
+
+            if let Event::Key(key) = event::read()? {
+                if key.kind != KeyEventKind::Press {
+                    continue;
+                }
+                app.status.clear();
+                if !app.handle_key(key) {
+                    return Ok(());
+                }
+            }
+        }
+    })();
+
+    ratatui::restore();
+    result
+}
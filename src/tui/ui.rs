@@ -0,0 +1,271 @@
+use ratatui::{
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, Gauge, List, ListItem, ListState, Paragraph, Tabs},
+    Frame,
+};
+
+use crate::{
+    models::{
+        download::DownloadState,
+        translation::{TranslationArg, Translations},
+    },
+    runtime::RuntimeTrait,
+    tui::app::{App, InputPurpose, Pane, SettingsField},
+};
+
+pub fn draw(frame: &mut Frame, app: &App) {
+    let [tabs_area, main_area, input_area, status_area] = Layout::vertical([
+        Constraint::Length(3),
+        Constraint::Min(0),
+        Constraint::Length(3),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+
+    let panes = Pane::all();
+    let titles: Vec<String> = panes
+        .iter()
+        .enumerate()
+        .map(|(idx, pane)| format!("F{} {}", idx + 1, Translations::t(pane.title_key(), None)))
+        .collect();
+    let tabs = Tabs::new(titles)
+        .select(panes.iter().position(|p| *p == app.pane).unwrap_or(0))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("YouTube Downloader"),
+        )
+        .highlight_style(highlight());
+    frame.render_widget(tabs, tabs_area);
+
+    match app.pane {
+        Pane::Queue => draw_queue(frame, app, main_area),
+        Pane::Downloads => draw_downloads(frame, app, main_area),
+        Pane::History => draw_history(frame, app, main_area),
+        Pane::Settings => draw_settings(frame, app, main_area),
+    }
+
+    draw_input(frame, app, input_area);
+
+    let status = if app.status.is_empty() {
+        Translations::t(app.pane.help_key(), None)
+    } else {
+        app.status.clone()
+    };
+    frame.render_widget(Paragraph::new(status), status_area);
+}
+
+fn highlight() -> Style {
+    Style::default()
+        .fg(Color::Black)
+        .bg(Color::Cyan)
+        .add_modifier(Modifier::BOLD)
+}
+
+fn pane_block(pane: Pane) -> Block<'static> {
+    Block::default()
+        .borders(Borders::ALL)
+        .title(Translations::t(pane.title_key(), None))
+}
+
+fn draw_queue(frame: &mut Frame, app: &App, area: Rect) {
+    let buffer = app.runtime.get_url_buffer();
+    let items: Vec<ListItem> = buffer
+        .iter()
+        .enumerate()
+        .map(|(i, video)| {
+            let title = video.title.clone().unwrap_or_else(|| "Unknown".to_string());
+            ListItem::new(format!("{}. {}", i + 1, title))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(pane_block(Pane::Queue))
+        .highlight_style(highlight());
+    let mut state =
+        ListState::default().with_selected((!buffer.is_empty()).then_some(app.queue_selected));
+    frame.render_stateful_widget(list, area, &mut state);
+}
+
+fn draw_downloads(frame: &mut Frame, app: &App, area: Rect) {
+    let block = pane_block(Pane::Downloads);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let downloads = app.runtime.get_downloads();
+    if downloads.is_empty() {
+        frame.render_widget(
+            Paragraph::new(Translations::t("tui_no_downloads", None)),
+            inner,
+        );
+        return;
+    }
+
+    // Keep the first unfinished item in view on long batches, unless the
+    // selection is somewhere else
+    let rows = inner.height as usize;
+    let first_active = downloads.iter().position(|d| d.is_active()).unwrap_or(0);
+    let selected = app.downloads_selected.min(downloads.len() - 1);
+    let offset = first_active
+        .min(downloads.len().saturating_sub(rows))
+        .min(selected)
+        .max((selected + 1).saturating_sub(rows));
+
+    let row_areas = Layout::vertical(vec![Constraint::Length(1); rows]).split(inner);
+    for ((idx, download), row) in downloads
+        .iter()
+        .enumerate()
+        .skip(offset)
+        .zip(row_areas.iter())
+    {
+        let [title_area, gauge_area] =
+            Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)])
+                .areas(*row);
+
+        let title = download
+            .music
+            .title
+            .clone()
+            .unwrap_or_else(|| "Unknown".to_string());
+        let title = match download.warnings.is_empty() {
+            true => title,
+            false => format!("{} ⚠ {}", title, download.warnings.join("; ")),
+        };
+        let mut title = Paragraph::new(title);
+        if idx == selected {
+            title = title.style(highlight());
+        }
+        frame.render_widget(title, title_area);
+
+        let (ratio, color) = match &download.state {
+            DownloadState::Waiting => (0.0, Color::DarkGray),
+            DownloadState::Scheduled => (0.0, Color::Blue),
+            DownloadState::Downloading(percent) => (f64::from(*percent) / 100.0, Color::Cyan),
+            DownloadState::Finished(_) => (1.0, Color::Green),
+            DownloadState::Failed(_) => (1.0, Color::Red),
+            DownloadState::Paused => (0.0, Color::Yellow),
+            DownloadState::Cancelled => (0.0, Color::DarkGray),
+        };
+        let gauge = Gauge::default()
+            .gauge_style(Style::default().fg(color))
+            .ratio(ratio.clamp(0.0, 1.0))
+            .label(download.state.describe());
+        frame.render_widget(gauge, gauge_area);
+    }
+}
+
+fn draw_history(frame: &mut Frame, app: &App, area: Rect) {
+    let entries = app.filtered_history();
+    let items: Vec<ListItem> = entries
+        .iter()
+        .map(|video| {
+            let title = video.title.clone().unwrap_or_else(|| "Unknown".to_string());
+            let date: TranslationArg = match video.downloaded_date() {
+                Some(date) => date.into(),
+                None => video.downloaded_at.clone().unwrap_or_default().into(),
+            };
+            ListItem::new(Translations::t(
+                "tui_history_entry",
+                Some(&[("title", title.into()), ("date", date)]),
+            ))
+        })
+        .collect();
+
+    let mut block = pane_block(Pane::History);
+    if !app.history_search.is_empty() {
+        block = block.title_bottom(Line::from(Translations::t(
+            "tui_search_results",
+            Some(&[
+                ("search", (&app.history_search).into()),
+                ("count", entries.len().into()),
+            ]),
+        )));
+    }
+
+    let list = List::new(items).block(block).highlight_style(highlight());
+    let mut state =
+        ListState::default().with_selected((!entries.is_empty()).then_some(app.history_selected));
+    frame.render_stateful_widget(list, area, &mut state);
+}
+
+fn draw_settings(frame: &mut Frame, app: &App, area: Rect) {
+    let on_off = |enabled: bool| match enabled {
+        true => Translations::t("tui_on", None),
+        false => Translations::t("tui_off", None),
+    };
+
+    let items: Vec<ListItem> = SettingsField::all()
+        .into_iter()
+        .map(|field| {
+            let (label, value) = match field {
+                SettingsField::Language => (
+                    Translations::t("settings_language", None),
+                    Translations::t(
+                        &format!(
+                            "language_{}",
+                            app.config.language.to_string().to_lowercase()
+                        ),
+                        None,
+                    ),
+                ),
+                SettingsField::Directory => (
+                    Translations::t("settings_set_directory", None),
+                    app.config.download_dir.clone(),
+                ),
+                SettingsField::Coloring => (
+                    Translations::t("settings_coloring", None),
+                    on_off(app.config.coloring),
+                ),
+                SettingsField::MaxRate => (
+                    Translations::t("settings_max_rate", None),
+                    app.config
+                        .limits
+                        .max_rate
+                        .map(|rate| rate.to_string())
+                        .unwrap_or_else(|| on_off(false)),
+                ),
+                SettingsField::Window => (
+                    Translations::t("settings_window", None),
+                    app.config
+                        .limits
+                        .window
+                        .map(|window| window.to_string())
+                        .unwrap_or_else(|| on_off(false)),
+                ),
+            };
+            ListItem::new(format!("{}: {}", label, value))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(pane_block(Pane::Settings))
+        .highlight_style(highlight());
+    let mut state = ListState::default().with_selected(Some(app.settings_selected));
+    frame.render_stateful_widget(list, area, &mut state);
+}
+
+fn draw_input(frame: &mut Frame, app: &App, area: Rect) {
+    let (title, value) = match &app.input {
+        Some(input) => {
+            let title_key = match input.purpose {
+                InputPurpose::AddUrl => "tui_input_url",
+                InputPurpose::Search => "tui_input_search",
+                InputPurpose::Directory => "tui_input_directory",
+                InputPurpose::MaxRate => "tui_input_max_rate",
+                InputPurpose::Window => "tui_input_window",
+            };
+            (Translations::t(title_key, None), input.value.as_str())
+        }
+        None => (String::new(), ""),
+    };
+
+    let block = Block::default().borders(Borders::ALL).title(title);
+    frame.render_widget(Paragraph::new(value).block(block), area);
+
+    if app.input.is_some() {
+        let x = area.x + 1 + value.chars().count() as u16;
+        frame.set_cursor_position((x.min(area.right().saturating_sub(2)), area.y + 1));
+    }
+}
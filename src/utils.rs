@@ -1,11 +1,20 @@
 use std::io::{self, Write};
 
 pub fn read_line(question: String) -> String {
+    try_read_line(question).unwrap_or_default()
+}
+
+// Like `read_line`, but returns `None` when stdin is closed (EOF / Ctrl-D)
+pub fn try_read_line(question: String) -> Option<String> {
     print!("{}", question);
     io::stdout().flush().unwrap();
 
     let mut choice = String::new();
-    io::stdin().read_line(&mut choice).unwrap();
-
-    choice.trim().to_string()
+    match io::stdin().read_line(&mut choice) {
+        Ok(0) | Err(_) => {
+            println!();
+            None
+        }
+        Ok(_) => Some(choice.trim().to_string()),
+    }
 }
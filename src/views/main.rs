@@ -1,30 +1,34 @@
-use std::sync::{Arc, Mutex};
-
-use rayon::prelude::*;
-
 use crate::{
     app_config::Config,
-    downloader::{download_video, fetch_playlist_videos},
-    models::{history::History, music::Music, translation::Translations},
+    doctor::Report,
+    downloader::{download_batch, fetch_playlist_videos},
+    models::{
+        download::DownloadState,
+        music::Music,
+        translation::{TranslationArg, Translations},
+    },
     runtime::RuntimeTrait,
     utils::read_line,
     views::{
+        prompt::{prompt, MenuOption},
         settings::{SettingsMenuOption, SettingsView},
         View,
     },
 };
+use std::time::Duration;
 
-pub struct MainView(Vec<MainViewOption>);
+pub struct MainView(Vec<MenuOption<MainMenuOption>>);
 impl MainView {
     pub fn new() -> Self {
         Self(vec![
-            MainViewOption::new(MainMenuOption::AddUrl, "menu_add_url"),
-            MainViewOption::new(MainMenuOption::ListQueue, "menu_list_queue"),
-            MainViewOption::new(MainMenuOption::Download, "menu_start_downloads"),
-            MainViewOption::new(MainMenuOption::ViewHistory, "menu_view_history"),
-            MainViewOption::new(MainMenuOption::ClearQueue, "menu_clear_queue"),
-            MainViewOption::new(MainMenuOption::Settings, "menu_settings"),
-            MainViewOption::new(MainMenuOption::Exit, "menu_exit"),
+            MenuOption::new(MainMenuOption::AddUrl, "menu_add_url", Some('a')),
+            MenuOption::new(MainMenuOption::ListQueue, "menu_list_queue", Some('l')),
+            MenuOption::new(MainMenuOption::Download, "menu_start_downloads", Some('d')),
+            MenuOption::new(MainMenuOption::ViewHistory, "menu_view_history", Some('h')),
+            MenuOption::new(MainMenuOption::ClearQueue, "menu_clear_queue", Some('c')),
+            MenuOption::new(MainMenuOption::Settings, "menu_settings", Some('s')),
+            MenuOption::new(MainMenuOption::Doctor, "menu_doctor", Some('r')),
+            MenuOption::new(MainMenuOption::Exit, "menu_exit", Some('q')),
         ])
     }
 }
@@ -34,49 +38,26 @@ impl View for MainView {
 
     fn render_view(&self) -> Self::Output {
         println!("\n{}", Translations::t("menu_title", None));
-        for main_option in &self.0 {
-            println!("{}", Translations::t(&main_option.display_value, None))
-        }
 
-        let input: i8 = read_line(Translations::t("menu_enter_choice", None))
-            .parse()
-            .unwrap();
-        if &(input as usize) > &self.0.len() && input <= 0 {
-            println!("{}", Translations::t("invalid_choice", Some(&["1", "7"])));
-            self.render_view();
-        }
-
-        MainMenuOption::from(input)
+        // Closing the input (Ctrl-D) quits the application
+        prompt(&self.0, "menu_enter_choice").unwrap_or(MainMenuOption::Exit)
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum MainMenuOption {
-    AddUrl = 1,
+    AddUrl,
     ListQueue,
     Download,
     ViewHistory,
     ClearQueue,
     Settings,
+    Doctor,
     Exit,
 }
 
-impl From<i8> for MainMenuOption {
-    fn from(value: i8) -> Self {
-        match value {
-            1 => MainMenuOption::AddUrl,
-            2 => MainMenuOption::ListQueue,
-            3 => MainMenuOption::Download,
-            4 => MainMenuOption::ViewHistory,
-            5 => MainMenuOption::ClearQueue,
-            6 => MainMenuOption::Settings,
-            7 => MainMenuOption::Exit,
-            _ => panic!("Invalid value for MainMenuOption"),
-        }
-    }
-}
-
 impl MainMenuOption {
-    pub fn create_menu<R: RuntimeTrait>(&self, runtime: &mut R) {
+    pub fn create_menu<R: RuntimeTrait + Sync>(&self, runtime: &mut R) {
         let config = Config::load();
         match &self {
             MainMenuOption::AddUrl => {
@@ -86,6 +67,20 @@ impl MainMenuOption {
                     println!("{}", Translations::t("fetching_info", None));
                     match fetch_playlist_videos(&url) {
                         Ok(videos) => {
+                            if let Some(playlist) =
+                                videos.first().and_then(|video| video.playlist.clone())
+                            {
+                                println!(
+                                    "{}",
+                                    Translations::t(
+                                        "found_playlist",
+                                        Some(&[
+                                            ("title", playlist.into()),
+                                            ("count", videos.len().into()),
+                                        ])
+                                    )
+                                );
+                            }
                             let mut buffer = runtime.get_url_buffer();
                             for video in videos {
                                 if buffer.contains(&video) {
@@ -94,14 +89,23 @@ impl MainMenuOption {
                                 }
                                 let title =
                                     video.title.clone().unwrap_or_else(|| "Unknown".to_string());
-                                println!("{}", Translations::t("added_to_queue", Some(&[&title])));
+                                println!(
+                                    "{}",
+                                    Translations::t(
+                                        "added_to_queue",
+                                        Some(&[("title", title.into())])
+                                    )
+                                );
                                 buffer.push_back(video);
                             }
                         }
                         Err(e) => {
                             println!(
                                 "{}",
-                                Translations::t("error_fetching", Some(&[&e.to_string()]))
+                                Translations::t(
+                                    "error_fetching",
+                                    Some(&[("error", e.to_string().into())])
+                                )
                             )
                         }
                     }
@@ -127,66 +131,77 @@ impl MainMenuOption {
                 } else {
                     println!(
                         "{}",
-                        Translations::t("starting_download", Some(&[&urls.len().to_string()]))
+                        Translations::t("starting_download", Some(&[("count", urls.len().into())]))
                     );
                     let dir = config.get_download_dir().to_string();
+                    let total = urls.len();
 
-                    let successful_downloads = Arc::new(Mutex::new(Vec::new()));
-                    let successful_clone = Arc::clone(&successful_downloads);
-
-                    let results: Vec<Result<(), Box<dyn std::error::Error + Send + Sync>>> = urls
-                        .par_iter()
-                        .map(|video| {
-                            if let Ok(()) = download_video(video, &dir) {
-                                successful_clone.lock().unwrap().push(video.clone());
-                                Ok(())
-                            } else {
-                                Err("Download failed".into())
-                            }
-                        })
-                        .collect();
-
-                    let success_count = results.iter().filter(|r| r.is_ok()).count();
-                    let fail_count = results.len() - success_count;
+                    let summary = download_batch(runtime, urls, &dir, print_download_state);
+                    let success_count = summary.successful.len();
 
                     println!("\n{}", Translations::t("download_summary", None));
                     println!(
                         "{}",
                         Translations::t(
                             "download_success",
-                            Some(&[&success_count.to_string(), &urls.len().to_string()])
+                            Some(&[("count", success_count.into()), ("total", total.into())])
                         )
                     );
                     println!(
                         "{}",
-                        Translations::t("download_fail", Some(&[&fail_count.to_string()]))
+                        Translations::t("download_fail", Some(&[("count", summary.failed.into())]))
                     );
-
-                    // Add successful downloads to history
-                    let successful = successful_downloads.lock().unwrap();
-                    if !successful.is_empty() {
-                        let mut history = History::load();
-                        for video in successful.iter() {
-                            history.add(video);
+                    if summary.paused > 0 {
+                        println!(
+                            "{}",
+                            Translations::t(
+                                "download_paused",
+                                Some(&[("count", summary.paused.into())])
+                            )
+                        );
+                    }
+                    if summary.cancelled > 0 {
+                        println!(
+                            "{}",
+                            Translations::t(
+                                "download_cancelled",
+                                Some(&[("count", summary.cancelled.into())])
+                            )
+                        );
+                    }
+                    for download in runtime.get_downloads().iter() {
+                        for warning in &download.warnings {
+                            print_download_warning(&download.music, warning);
                         }
                     }
                 }
             }
             MainMenuOption::ViewHistory => {
-                let history = History::load();
+                let history = runtime.get_history();
                 if history.downloads.is_empty() {
                     println!("{}", Translations::t("no_history", None));
                 } else {
                     println!("\n{}", Translations::t("history_title", None));
                     for (i, video) in history.downloads.iter().enumerate().rev().take(10) {
                         let title = video.title.clone().unwrap_or_else(|| "Unknown".to_string());
-                        let date = video
-                            .downloaded_at
-                            .clone()
-                            .unwrap_or_else(|| "Unknown".to_string());
+                        let date: TranslationArg = match video.downloaded_date() {
+                            Some(date) => date.into(),
+                            None => video
+                                .downloaded_at
+                                .clone()
+                                .unwrap_or_else(|| "Unknown".to_string())
+                                .into(),
+                        };
                         println!(
-                            "{}. {title} - Downloaded on {date}",
-                            history.downloads.len() - i
+                            "{}",
+                            Translations::t(
+                                "history_entry",
+                                Some(&[
+                                    ("index", (history.downloads.len() - i).into()),
+                                    ("title", title.into()),
+                                    ("date", date)
+                                ])
+                            )
                         );
                     }
 
@@ -195,7 +210,7 @@ impl MainMenuOption {
                             "{}",
                             Translations::t(
                                 "history_more",
-                                Some(&[&(history.downloads.len() - 10).to_string()])
+                                Some(&[("count", (history.downloads.len() - 10).into())])
                             )
                         );
                     }
@@ -206,7 +221,7 @@ impl MainMenuOption {
                 runtime.clear_url_buffer();
                 println!(
                     "{}",
-                    Translations::t("queue_cleared", Some(&[&count.to_string()]))
+                    Translations::t("queue_cleared", Some(&[("count", count.into())]))
                 );
             }
             MainMenuOption::Settings => {
@@ -215,6 +230,9 @@ impl MainMenuOption {
 
                 SettingsMenuOption::create_menu(&setting_choice);
             }
+            MainMenuOption::Doctor => {
+                println!("\n{}", Report::collect(&config));
+            }
             MainMenuOption::Exit => {
                 println!("{}", Translations::t("exiting", None));
                 runtime.stop();
@@ -223,15 +241,55 @@ impl MainMenuOption {
     }
 }
 
-pub struct MainViewOption {
-    _option: MainMenuOption,
-    pub display_value: String,
+// A step of the download that failed without failing it
+pub fn print_download_warning(video: &Music, warning: &str) {
+    let title = video.title.clone().unwrap_or_else(|| "Unknown".to_string());
+    println!(
+        "{}",
+        Translations::t(
+            "download_warning",
+            Some(&[("title", title.into()), ("warning", warning.into())])
+        )
+    );
 }
-impl MainViewOption {
-    pub fn new(_option: MainMenuOption, display_value: &str) -> Self {
-        Self {
-            _option,
-            display_value: display_value.to_string(),
-        }
+
+// Print the progress messages of the classic menu
+pub fn print_download_state(video: &Music, state: &DownloadState) {
+    let title = video.title.clone().unwrap_or_else(|| "Unknown".to_string());
+    match state {
+        DownloadState::Downloading(_) => println!(
+            "{}",
+            Translations::t("video_downloading", Some(&[("title", title.into())]))
+        ),
+        DownloadState::Finished(seconds) => println!(
+            "{}",
+            Translations::t(
+                "video_downloaded",
+                Some(&[
+                    ("title", title.into()),
+                    ("duration", Duration::from_secs(*seconds).into())
+                ])
+            )
+        ),
+        DownloadState::Failed(error) => println!(
+            "{}",
+            Translations::t(
+                "video_download_failed",
+                Some(&[("title", title.into()), ("error", error.into())])
+            )
+        ),
+        DownloadState::Paused => println!(
+            "{}",
+            Translations::t("video_paused", Some(&[("title", title.into())]))
+        ),
+        DownloadState::Cancelled => println!(
+            "{}",
+            Translations::t("video_cancelled", Some(&[("title", title.into())]))
+        ),
+        DownloadState::Scheduled => println!(
+            "{}",
+            Translations::t("video_scheduled", Some(&[("title", title.into())]))
+        ),
+        DownloadState::Waiting => {}
     }
 }
@@ -1,5 +1,6 @@
 pub mod languages;
 pub mod main;
+pub mod prompt;
 pub mod settings;
 
 pub trait View {
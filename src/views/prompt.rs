@@ -0,0 +1,67 @@
+use crate::{
+    models::translation::{TranslationArgs, Translations},
+    utils::try_read_line,
+};
+
+// A menu entry; its number comes from its position in the menu
+#[derive(Debug)]
+pub struct MenuOption<T> {
+    pub option: T,
+    pub display_value: String,
+    pub hotkey: Option<char>,
+}
+impl<T: Copy> MenuOption<T> {
+    pub fn new(option: T, display_value: &str, hotkey: Option<char>) -> Self {
+        Self {
+            option,
+            display_value: display_value.to_string(),
+            hotkey,
+        }
+    }
+}
+
+// Print the options and ask until a valid number or hotkey is entered.
+// Returns `None` when the input is closed (EOF / Ctrl-D).
+pub fn prompt<T: Copy>(options: &[MenuOption<T>], question_key: &str) -> Option<T> {
+    print_options(options);
+
+    let range: &TranslationArgs = &[("min", 1usize.into()), ("max", options.len().into())];
+
+    loop {
+        let input = try_read_line(Translations::t(question_key, Some(range)))?;
+
+        match find_option(options, &input) {
+            Some(option) => return Some(option),
+            None => println!("{}", Translations::t("invalid_choice", Some(range))),
+        }
+    }
+}
+
+// Print menu entries numbered by their position, followed by their hotkey
+fn print_options<T>(options: &[MenuOption<T>]) {
+    for (idx, option) in options.iter().enumerate() {
+        let label = Translations::t(&option.display_value, None);
+        match option.hotkey {
+            Some(hotkey) => println!("{}. {} [{}]", idx + 1, label, hotkey),
+            None => println!("{}. {}", idx + 1, label),
+        }
+    }
+}
+
+// Find the entry chosen either by its 1-based number or by its hotkey
+fn find_option<T: Copy>(options: &[MenuOption<T>], input: &str) -> Option<T> {
+    let input = input.trim();
+    let number = input.parse::<usize>().ok();
+    let letter = match input.chars().count() {
+        1 => input.chars().next().map(|c| c.to_ascii_lowercase()),
+        _ => None,
+    };
+
+    options
+        .iter()
+        .enumerate()
+        .find(|(idx, option)| {
+            number == Some(idx + 1) || (letter.is_some() && option.hotkey == letter)
+        })
+        .map(|(_, option)| option.option)
+}
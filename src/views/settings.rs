@@ -2,22 +2,32 @@ use rfd::FileDialog;
 
 use crate::{
     app_config::Config,
-    models::translation::Translations,
+    models::{
+        limits::{DownloadWindow, Rate},
+        translation::Translations,
+    },
     utils::read_line,
     views::{
         languages::{LanguageMenuOption, LanguageView},
+        prompt::{prompt, MenuOption},
         View,
     },
 };
 
-pub struct SettingsView(Vec<SettingsViewOption>);
+pub struct SettingsView(Vec<MenuOption<SettingsMenuOption>>);
 impl SettingsView {
     pub fn new() -> Self {
         Self(vec![
-            SettingsViewOption::new(SettingsMenuOption::Language, "settings_language"),
-            SettingsViewOption::new(SettingsMenuOption::Directory, "settings_set_directory"),
-            SettingsViewOption::new(SettingsMenuOption::Coloring, "settings_coloring"),
-            SettingsViewOption::new(SettingsMenuOption::Back, "settings_back"),
+            MenuOption::new(SettingsMenuOption::Language, "settings_language", Some('l')),
+            MenuOption::new(
+                SettingsMenuOption::Directory,
+                "settings_set_directory",
+                Some('d'),
+            ),
+            MenuOption::new(SettingsMenuOption::Coloring, "settings_coloring", Some('c')),
+            MenuOption::new(SettingsMenuOption::MaxRate, "settings_max_rate", Some('r')),
+            MenuOption::new(SettingsMenuOption::Window, "settings_window", Some('w')),
+            MenuOption::new(SettingsMenuOption::Back, "settings_back", Some('b')),
         ])
     }
 }
@@ -28,41 +38,20 @@ impl View for SettingsView {
     fn render_view(&self) -> Self::Output {
         println!("\n{}", Translations::t("settings_title", None));
 
-        for option in &self.0 {
-            println!("{}", Translations::t(&option.display_value, None));
-        }
-
-        let input: i8 = read_line(Translations::t("settings_enter_choice", None))
-            .parse()
-            .unwrap();
-        if &(input as usize) > &self.0.len() && input <= 0 {
-            println!("{}", Translations::t("invalid_choice", Some(&["1", "4"])));
-            self.render_view();
-        }
-
-        SettingsMenuOption::from(input)
+        prompt(&self.0, "settings_enter_choice").unwrap_or(SettingsMenuOption::Back)
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum SettingsMenuOption {
-    Language = 1,
+    Language,
     Directory,
     Coloring,
+    MaxRate,
+    Window,
     Back,
 }
 
-impl From<i8> for SettingsMenuOption {
-    fn from(value: i8) -> Self {
-        match value {
-            1 => SettingsMenuOption::Language,
-            2 => SettingsMenuOption::Directory,
-            3 => SettingsMenuOption::Coloring,
-            4 => SettingsMenuOption::Back,
-            _ => panic!("Invalid value for SettingsMenuOption"),
-        }
-    }
-}
-
 impl SettingsMenuOption {
     pub fn create_menu(&self) {
         let mut config = Config::load();
@@ -90,7 +79,10 @@ impl SettingsMenuOption {
                 if let Some(new_dir) = FileDialog::new().pick_folder() {
                     let dir_str = new_dir.display().to_string();
                     config.set_download_dir(dir_str.clone()).unwrap();
-                    println!("{}", Translations::t("dir_set", Some(&[&dir_str])));
+                    println!(
+                        "{}",
+                        Translations::t("dir_set", Some(&[("dir", dir_str.into())]))
+                    );
                 } else {
                     println!("{}", Translations::t("no_dir_selected", None));
                 }
@@ -103,20 +95,57 @@ impl SettingsMenuOption {
 
                 println!("{}", Translations::t("coloring_toggled", None));
             }
-            SettingsMenuOption::Back => println!("{}", Translations::t("return_to_menu", None)),
-        }
-    }
-}
+            SettingsMenuOption::MaxRate => {
+                let input = read_line(Translations::t("enter_max_rate", None));
+                let mut limits = config.limits;
 
-pub struct SettingsViewOption {
-    _option: SettingsMenuOption,
-    pub display_value: String,
-}
-impl SettingsViewOption {
-    pub fn new(_option: SettingsMenuOption, display_value: &str) -> Self {
-        Self {
-            _option,
-            display_value: display_value.to_string(),
+                if input.is_empty() {
+                    limits.max_rate = None;
+                    config.set_limits(limits).unwrap();
+                    println!("{}", Translations::t("max_rate_cleared", None));
+                } else {
+                    match input.parse::<Rate>() {
+                        Ok(rate) => {
+                            limits.max_rate = Some(rate);
+                            config.set_limits(limits).unwrap();
+                            println!(
+                                "{}",
+                                Translations::t(
+                                    "max_rate_set",
+                                    Some(&[("rate", rate.to_string().into())])
+                                )
+                            );
+                        }
+                        Err(_) => println!("{}", Translations::t("invalid_max_rate", None)),
+                    }
+                }
+            }
+            SettingsMenuOption::Window => {
+                let input = read_line(Translations::t("enter_window", None));
+                let mut limits = config.limits;
+
+                if input.is_empty() {
+                    limits.window = None;
+                    config.set_limits(limits).unwrap();
+                    println!("{}", Translations::t("window_cleared", None));
+                } else {
+                    match input.parse::<DownloadWindow>() {
+                        Ok(window) => {
+                            limits.window = Some(window);
+                            config.set_limits(limits).unwrap();
+                            println!(
+                                "{}",
+                                Translations::t(
+                                    "window_set",
+                                    Some(&[("window", window.to_string().into())])
+                                )
+                            );
+                        }
+                        Err(_) => println!("{}", Translations::t("invalid_window", None)),
+                    }
+                }
+            }
+            SettingsMenuOption::Back => println!("{}", Translations::t("return_to_menu", None)),
         }
     }
 }